@@ -14,7 +14,49 @@
 * along with this program. If not, see <https://www.gnu.org/licenses/>.
 */
 
+use std::env;
+use std::path::{Path, PathBuf};
+
+//BASS_LIB_DIR is searched first, then this crate's directory and the standard prefixes
+
 fn main() {
-    println!("cargo:rustc-link-search=/usr/lib");
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BASS_LIB_DIR");
+
+    //With the dlopen feature, the library is loaded at runtime instead
+    if env::var_os("CARGO_FEATURE_DLOPEN").is_some() {
+        return;
+        }
+
+    let target_os=env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let library_name=match target_os.as_str() {
+        "windows" => "bass.lib",
+        "macos" => "libbass.dylib",
+        _ => "libbass.so",
+        };
+
+    match library_directories(&target_os).into_iter().find(|directory| directory.join(library_name).is_file()) {
+        Some(directory) => println!("cargo:rustc-link-search=native={}", directory.display()),
+        None => println!("cargo:warning={} not found, set BASS_LIB_DIR to the directory containing it, or enable the dlopen feature", library_name),
+        }
+
     println!("cargo:rustc-link-lib=bass");
     }
+
+fn library_directories(target_os: &str) -> Vec<PathBuf> {
+    let mut directories=Vec::new();
+
+    if let Some(directory)=env::var_os("BASS_LIB_DIR") {
+        directories.push(PathBuf::from(directory));
+        }
+    if let Some(directory)=env::var_os("CARGO_MANIFEST_DIR") {
+        directories.push(PathBuf::from(directory));
+        }
+
+    if target_os!="windows" {
+        let prefixes=["/usr/local/lib", "/usr/lib", "/usr/lib64", "/usr/lib/x86_64-linux-gnu", "/usr/lib/aarch64-linux-gnu", "/opt/homebrew/lib"];
+        directories.extend(prefixes.iter().map(|prefix| Path::new(prefix).to_path_buf()));
+        }
+
+    directories
+    }
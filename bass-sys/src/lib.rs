@@ -17,11 +17,181 @@
 use std::ffi::c_void;
 use std::os::raw::c_char;
 
-extern {
+pub const BASS_OK: i32=0;
+pub const BASS_ERROR_MEM: i32=1;
+pub const BASS_ERROR_FILEOPEN: i32=2;
+pub const BASS_ERROR_DRIVER: i32=3;
+pub const BASS_ERROR_HANDLE: i32=5;
+pub const BASS_ERROR_FORMAT: i32=6;
+pub const BASS_ERROR_INIT: i32=8;
+pub const BASS_ERROR_START: i32=9;
+pub const BASS_ERROR_ALREADY: i32=14;
+pub const BASS_ERROR_NOTAUDIO: i32=17;
+pub const BASS_ERROR_NOCHAN: i32=18;
+pub const BASS_ERROR_ILLTYPE: i32=19;
+pub const BASS_ERROR_ILLPARAM: i32=20;
+pub const BASS_ERROR_DEVICE: i32=23;
+pub const BASS_ERROR_NOPLAY: i32=24;
+pub const BASS_ERROR_FREQ: i32=25;
+pub const BASS_ERROR_NOTFILE: i32=27;
+pub const BASS_ERROR_EMPTY: i32=31;
+pub const BASS_ERROR_CREATE: i32=33;
+pub const BASS_ERROR_NOTAVAIL: i32=37;
+pub const BASS_ERROR_FILEFORM: i32=41;
+pub const BASS_ERROR_CODEC: i32=44;
+pub const BASS_ERROR_BUSY: i32=46;
+pub const BASS_ERROR_UNKNOWN: i32=-1;
+
+pub const BASS_ATTRIB_VOL: u32=2;
+pub const BASS_ATTRIB_PAN: u32=3;
+
+pub const BASS_SAMPLE_OVER_POS: u32=0x20000;
+
+pub const BASS_CONFIG_GVOL_SAMPLE: u32=4;
+
+//Not a BASS error, reported with the dlopen feature when the library couldn't be loaded
+pub const BASS_ERROR_NOLIBRARY: i32=-2;
+
+//Declares the functions either as linked, or loaded from the library on the first call with the dlopen feature
+
+macro_rules! bass_functions {
+    ($(pub fn $name:ident($($argument:ident: $type:ty),*) -> $result:ty;)*) => {
+        #[cfg(not(feature="dlopen"))]
+        extern "C" {
+            $(pub fn $name($($argument: $type),*) -> $result;)*
+            }
+
+        #[cfg(feature="dlopen")]
+        #[allow(non_snake_case)]
+        struct Functions {
+            _library: libloading::Library,
+            $($name: unsafe extern "C" fn($($type),*) -> $result,)*
+            }
+
+        #[cfg(feature="dlopen")]
+        #[allow(non_snake_case)]
+        impl Functions {
+
+            fn load() -> Option<Functions> {
+                let library=library_paths().iter().find_map(|path| unsafe { libloading::Library::new(path) }.ok())?;
+
+                unsafe {
+                    $(let $name=*library.get::<unsafe extern "C" fn($($type),*) -> $result>(concat!(stringify!($name), "\0").as_bytes()).ok()?;)*
+
+                    Some(Functions {_library: library, $($name,)*})
+                    }
+                }
+
+            }
+
+        $(
+        #[cfg(feature="dlopen")]
+        #[allow(non_snake_case, clippy::missing_safety_doc)]
+        pub unsafe fn $name($($argument: $type),*) -> $result {
+            match functions() {
+                Some(functions) => (functions.$name)($($argument),*),
+                None => Unavailable::unavailable(),
+                }
+            }
+        )*
+        };
+    }
+
+bass_functions! {
     pub fn BASS_Init(device: i32, freq: u32, flags: u32, p1: *mut c_void, p2: *mut c_void) -> bool;
+    pub fn BASS_Free() -> bool;
+    pub fn BASS_SetConfig(option: u32, value: u32) -> bool;
     pub fn BASS_ErrorGetCode() -> i32;
     pub fn BASS_SampleLoad(mem: bool, file: *const c_char, offset: u64, length: u32, max: u32, flags: u32) -> u32;
     pub fn BASS_SampleGetChannel(handle: u32, onlynew: bool) -> u32;
+    pub fn BASS_SampleCreate(length: u32, freq: u32, chans: u32, max: u32, flags: u32) -> u32;
+    pub fn BASS_SampleSetData(handle: u32, buffer: *const c_void) -> bool;
+    pub fn BASS_SampleFree(handle: u32) -> bool;
     pub fn BASS_ChannelPlay(handle: u32, restart: bool) -> bool;
+    pub fn BASS_ChannelSetAttribute(handle: u32, attrib: u32, value: f32) -> bool;
+    }
+
+#[cfg(all(feature="dlopen", target_os="windows"))]
+const LIBRARY_NAME: &str="bass.dll";
+#[cfg(all(feature="dlopen", target_os="macos"))]
+const LIBRARY_NAME: &str="libbass.dylib";
+#[cfg(all(feature="dlopen", not(any(target_os="windows", target_os="macos"))))]
+const LIBRARY_NAME: &str="libbass.so";
+
+//The library next to the executable is preferred, then the system search path is used
+
+#[cfg(feature="dlopen")]
+fn library_paths() -> Vec<std::ffi::OsString> {
+    let mut paths=Vec::new();
+
+    if let Some(directory)=std::env::current_exe().ok().as_ref().and_then(|path| path.parent()) {
+        paths.push(directory.join(LIBRARY_NAME).into_os_string());
+        }
+    paths.push(LIBRARY_NAME.into());
+
+    paths
+    }
+
+#[cfg(feature="dlopen")]
+fn functions() -> Option<&'static Functions> {
+    static FUNCTIONS: std::sync::OnceLock<Option<Functions>>=std::sync::OnceLock::new();
+
+    FUNCTIONS.get_or_init(Functions::load).as_ref()
+    }
+
+//What the functions return when the library isn't available, failures and BASS_ERROR_NOLIBRARY
+
+#[cfg(feature="dlopen")]
+trait Unavailable {
+    fn unavailable() -> Self;
+    }
+
+#[cfg(feature="dlopen")]
+impl Unavailable for bool {
+
+    fn unavailable() -> Self {
+        false
+        }
+
+    }
+
+#[cfg(feature="dlopen")]
+impl Unavailable for u32 {
+
+    fn unavailable() -> Self {
+        0
+        }
+
+    }
+
+#[cfg(feature="dlopen")]
+impl Unavailable for i32 {
+
+    fn unavailable() -> Self {
+        BASS_ERROR_NOLIBRARY
+        }
+
+    }
+
+#[cfg(all(test, feature="dlopen"))]
+mod tests {
+
+    use super::*;
+
+    //The library isn't searched for next to the test binary, so it's missing unless installed system wide
+
+    #[test]
+    fn missing_library_is_reported() {
+        unsafe {
+            if functions().is_none() {
+                assert_eq!(BASS_ErrorGetCode(), BASS_ERROR_NOLIBRARY);
+                assert!(!BASS_Init(-1, 44100, 0, std::ptr::null_mut(), std::ptr::null_mut()));
+                assert_eq!(BASS_SampleLoad(false, b"Bump.wav\0".as_ptr() as *const c_char, 0, 0, 1, 0), 0);
+                }
+            else {
+                assert_ne!(BASS_ErrorGetCode(), BASS_ERROR_NOLIBRARY);
+                }
+            }
+        }
 
     }
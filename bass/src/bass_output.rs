@@ -0,0 +1,148 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU Lesser General Public License as published by
+* the Free Software Foundation, version 2.1.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU Lesser General Public License for more details.
+*
+* You should have received a copy of the GNU Lesser General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::ffi::{c_void, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+use bass_sys::*;
+
+use crate::{BassError, Output, Sample, SAMPLE_RATE};
+
+//BASS is freed when the output is dropped
+//Once all voices of a sample play, the one played the longest is reused
+
+pub struct BassOutput {
+    max_voices: u32,
+    }
+
+impl BassOutput {
+
+    pub fn new(max_voices: u32) -> Result<BassOutput, BassError> {
+        check(unsafe { BASS_Init(-1, SAMPLE_RATE, 0, ptr::null_mut(), ptr::null_mut()) })?;
+
+        Ok(BassOutput {max_voices: max_voices.max(1)})
+        }
+
+    fn sample(handle: u32) -> Result<Box<dyn Sample>, BassError> {
+        if handle==0 {
+            return Err(BassError::last());
+            }
+
+        Ok(Box::new(BassSample {handle}))
+        }
+
+    }
+
+impl Output for BassOutput {
+
+    fn name(&self) -> &str {
+        "bass"
+        }
+
+    fn load(&self, file_path: &str) -> Result<Box<dyn Sample>, BassError> {
+        let file_path=CString::new(file_path).map_err(|_| BassError::IllegalParameter)?;
+
+        BassOutput::sample(unsafe { BASS_SampleLoad(false, file_path.as_ptr(), 0, 0, self.max_voices, BASS_SAMPLE_OVER_POS) })
+        }
+
+    fn load_from_memory(&self, data: &[u8]) -> Result<Box<dyn Sample>, BassError> {
+        BassOutput::sample(unsafe { BASS_SampleLoad(true, data.as_ptr() as *const c_char, 0, data.len() as u32, self.max_voices, BASS_SAMPLE_OVER_POS) })
+        }
+
+    fn create(&self, samples: &[i16]) -> Result<Box<dyn Sample>, BassError> {
+        let handle=unsafe { BASS_SampleCreate((samples.len()*2) as u32, SAMPLE_RATE, 1, self.max_voices, BASS_SAMPLE_OVER_POS) };
+        //The sample is freed on drop also if setting its data fails
+        let sample=BassOutput::sample(handle)?;
+
+        check(unsafe { BASS_SampleSetData(handle, samples.as_ptr() as *const c_void) })?;
+
+        Ok(sample)
+        }
+
+    fn set_volume(&self, volume: f32) -> Result<(), BassError> {
+        check(unsafe { BASS_SetConfig(BASS_CONFIG_GVOL_SAMPLE, (volume*10000.0) as u32) })
+        }
+
+    }
+
+impl Drop for BassOutput {
+
+    fn drop(&mut self) {
+        unsafe {
+            BASS_Free();
+            }
+        }
+
+    }
+
+//Every play gets a channel of its own, so sounds played in a quick succession don't cut each other off
+
+struct BassSample {
+    handle: u32,
+    }
+
+impl Sample for BassSample {
+
+    fn play(&self, pan: f32, volume: f32) -> Result<(), BassError> {
+        let channel=unsafe { BASS_SampleGetChannel(self.handle, false) };
+
+        if channel==0 {
+            return Err(BassError::last());
+            }
+
+        check(unsafe { BASS_ChannelSetAttribute(channel, BASS_ATTRIB_PAN, pan) })?;
+        check(unsafe { BASS_ChannelSetAttribute(channel, BASS_ATTRIB_VOL, volume) })?;
+        check(unsafe { BASS_ChannelPlay(channel, false) })
+        }
+
+    }
+
+impl Drop for BassSample {
+
+    fn drop(&mut self) {
+        unsafe {
+            BASS_SampleFree(self.handle);
+            }
+        }
+
+    }
+
+fn check(result: bool) -> Result<(), BassError> {
+    if result {
+        Ok(())
+        }
+    else {
+        Err(BassError::last())
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn loading_without_initialization_fails() {
+        let output=BassOutput {max_voices: 1};
+
+        //With the dlopen feature, the library may be missing altogether
+        let error=output.load("/nonexistent/Bump.wav").err();
+        assert!(error==Some(BassError::Init) || error==Some(BassError::NoLibrary));
+        assert!(output.load_from_memory(&[]).is_err());
+        }
+
+    }
@@ -0,0 +1,138 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU Lesser General Public License as published by
+* the Free Software Foundation, version 2.1.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU Lesser General Public License for more details.
+*
+* You should have received a copy of the GNU Lesser General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::error::Error;
+use std::fmt;
+
+use bass_sys::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BassError {
+    Memory,
+    FileOpen,
+    Driver,
+    Handle,
+    Format,
+    Init,
+    Start,
+    AlreadyInitialized,
+    NotAudio,
+    NoChannel,
+    IllegalType,
+    IllegalParameter,
+    Device,
+    NoPlay,
+    Frequency,
+    NotFile,
+    Empty,
+    Create,
+    NotAvailable,
+    FileFormat,
+    Codec,
+    Busy,
+    NoLibrary,
+    Unknown(i32),
+    }
+
+impl BassError {
+
+    pub fn from_code(code: i32) -> BassError {
+        match code {
+            BASS_ERROR_MEM => BassError::Memory,
+            BASS_ERROR_FILEOPEN => BassError::FileOpen,
+            BASS_ERROR_DRIVER => BassError::Driver,
+            BASS_ERROR_HANDLE => BassError::Handle,
+            BASS_ERROR_FORMAT => BassError::Format,
+            BASS_ERROR_INIT => BassError::Init,
+            BASS_ERROR_START => BassError::Start,
+            BASS_ERROR_ALREADY => BassError::AlreadyInitialized,
+            BASS_ERROR_NOTAUDIO => BassError::NotAudio,
+            BASS_ERROR_NOCHAN => BassError::NoChannel,
+            BASS_ERROR_ILLTYPE => BassError::IllegalType,
+            BASS_ERROR_ILLPARAM => BassError::IllegalParameter,
+            BASS_ERROR_DEVICE => BassError::Device,
+            BASS_ERROR_NOPLAY => BassError::NoPlay,
+            BASS_ERROR_FREQ => BassError::Frequency,
+            BASS_ERROR_NOTFILE => BassError::NotFile,
+            BASS_ERROR_EMPTY => BassError::Empty,
+            BASS_ERROR_CREATE => BassError::Create,
+            BASS_ERROR_NOTAVAIL => BassError::NotAvailable,
+            BASS_ERROR_FILEFORM => BassError::FileFormat,
+            BASS_ERROR_CODEC => BassError::Codec,
+            BASS_ERROR_BUSY => BassError::Busy,
+            BASS_ERROR_NOLIBRARY => BassError::NoLibrary,
+            code => BassError::Unknown(code),
+            }
+        }
+
+    //The error of the last BASS call made by this thread
+
+    pub fn last() -> BassError {
+        BassError::from_code(unsafe { BASS_ErrorGetCode() })
+        }
+
+    }
+
+impl fmt::Display for BassError {
+
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BassError::Memory => write!(f, "Out of memory"),
+            BassError::FileOpen => write!(f, "The file could not be opened"),
+            BassError::Driver => write!(f, "No available audio driver"),
+            BassError::Handle => write!(f, "Invalid handle"),
+            BassError::Format => write!(f, "Unsupported sample format"),
+            BassError::Init => write!(f, "BASS is not initialized"),
+            BassError::Start => write!(f, "The output is paused or stopped"),
+            BassError::AlreadyInitialized => write!(f, "BASS is already initialized"),
+            BassError::NotAudio => write!(f, "The file does not contain audio"),
+            BassError::NoChannel => write!(f, "No free channel"),
+            BassError::IllegalType => write!(f, "Illegal type"),
+            BassError::IllegalParameter => write!(f, "Illegal parameter"),
+            BassError::Device => write!(f, "Illegal device number"),
+            BassError::NoPlay => write!(f, "Not playing"),
+            BassError::Frequency => write!(f, "Illegal sample rate"),
+            BassError::NotFile => write!(f, "Not a file stream"),
+            BassError::Empty => write!(f, "The file is empty"),
+            BassError::Create => write!(f, "The sample could not be created"),
+            BassError::NotAvailable => write!(f, "Not available"),
+            BassError::FileFormat => write!(f, "Unsupported file format"),
+            BassError::Codec => write!(f, "The codec is not available"),
+            BassError::Busy => write!(f, "The device is busy"),
+            BassError::NoLibrary => write!(f, "The BASS library could not be loaded"),
+            BassError::Unknown(code) => write!(f, "Unknown BASS error {}", code),
+            }
+        }
+
+    }
+
+impl Error for BassError {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn error_codes_are_mapped() {
+        assert_eq!(BassError::from_code(BASS_ERROR_FILEOPEN), BassError::FileOpen);
+        assert_eq!(BassError::from_code(BASS_ERROR_INIT), BassError::Init);
+        assert_eq!(BassError::from_code(BASS_ERROR_UNKNOWN), BassError::Unknown(-1));
+        assert_eq!(BassError::from_code(BASS_ERROR_NOLIBRARY), BassError::NoLibrary);
+        assert_eq!(BassError::FileOpen.to_string(), "The file could not be opened");
+        }
+
+    }
@@ -14,59 +14,168 @@
 * along with this program. If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::ffi::CString;
-use std::ptr;
+mod bass_output;
+mod error;
+mod silent;
 
-use bass_sys::*;
+use std::f32::consts::PI;
+use std::time::Duration;
+
+pub use bass_output::BassOutput;
+pub use error::BassError;
+pub use silent::SilentOutput;
+
+const SAMPLE_RATE: u32=44100;
+const TONE_AMPLITUDE: f32=0.5;
+const TONE_FADE: Duration=Duration::from_millis(5);
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Waveform {
+    Sine,
+    Square,
+    }
+
+//An output loads samples, which can then be played independently of it
+
+pub trait Output {
+    fn name(&self) -> &str;
+    fn load(&self, file_path: &str) -> Result<Box<dyn Sample>, BassError>;
+    fn load_from_memory(&self, data: &[u8]) -> Result<Box<dyn Sample>, BassError>;
+
+    //Mono 16-bit samples at 44100 Hz
+
+    fn create(&self, samples: &[i16]) -> Result<Box<dyn Sample>, BassError>;
+
+    //Volume ranges from 0, silent, to 1, the full volume, and applies to all samples
+
+    fn set_volume(&self, volume: f32) -> Result<(), BassError>;
+    }
+
+//Pan ranges from -1, the left, to 1, the right
+
+pub trait Sample {
+    fn play(&self, pan: f32, volume: f32) -> Result<(), BassError>;
+    }
 
 pub struct Sound {
-    sample: u32,
-    channel: u32,
+    sample: Box<dyn Sample>,
     }
 
 impl Sound {
 
-    pub fn init() -> bool {
-        unsafe {
-            BASS_Init(-1, 44100, 0, ptr::null_mut(), ptr::null_mut())
-            }
+    pub fn play(&self) -> Result<(), BassError> {
+        self.play_with(0.0, 1.0)
         }
 
-    pub fn get_error_code() -> i32 {
-        unsafe {
-            BASS_ErrorGetCode()
-            }
+    pub fn play_with(&self, pan: f32, volume: f32) -> Result<(), BassError> {
+        self.sample.play(pan, volume)
         }
 
-    pub fn new() -> Sound {
-        Sound {sample: 0, channel: 0}
+    }
+
+//Sounds should be dropped before the audio they were loaded by
+
+pub struct Audio {
+    output: Box<dyn Output>,
+    }
+
+impl Audio {
+
+    //Initializes BASS on the default device, each sound can be heard up to max_voices times at once
+
+    pub fn init(max_voices: u32) -> Result<Audio, BassError> {
+        Ok(Audio::with_output(Box::new(BassOutput::new(max_voices)?)))
         }
 
-    pub fn load(&mut self, file_path: &str) -> u32 {
-        let file_path=CString::new(file_path).unwrap();
-        unsafe {
-            self.sample=BASS_SampleLoad(false, file_path.as_ptr(), 0, 0, 1, 0);
+    //Sounds of the silent output load and play, but aren't heard
+
+    pub fn silent() -> Audio {
+        Audio::with_output(Box::new(SilentOutput))
+        }
 
-            if self.sample!=0 {
-                self.channel=BASS_SampleGetChannel(self.sample, false);
-                }
-            }
+    pub fn with_output(output: Box<dyn Output>) -> Audio {
+        Audio {output}
+        }
 
-        self.channel
+    pub fn name(&self) -> &str {
+        self.output.name()
         }
 
-    pub fn play(&self) {
-        unsafe {
-            BASS_ChannelPlay(self.channel, true);
-            }
+    pub fn load(&self, file_path: &str) -> Result<Sound, BassError> {
+        Ok(Sound {sample: self.output.load(file_path)?})
+        }
+
+    //The data is copied, so the buffer doesn't need to outlive the sound
+
+    pub fn load_from_memory(&self, data: &[u8]) -> Result<Sound, BassError> {
+        Ok(Sound {sample: self.output.load_from_memory(data)?})
+        }
+
+    pub fn generate(&self, waveform: Waveform, frequency: f32, duration: Duration) -> Result<Sound, BassError> {
+        Ok(Sound {sample: self.output.create(&tone_samples(waveform, frequency, duration))?})
+        }
+
+    pub fn set_volume(&self, volume: f32) -> Result<(), BassError> {
+        self.output.set_volume(volume.clamp(0.0, 1.0))
         }
 
     }
 
+//Faded in and out to avoid clicks
+
+fn tone_samples(waveform: Waveform, frequency: f32, duration: Duration) -> Vec<i16> {
+    let length=(duration.as_secs_f32()*SAMPLE_RATE as f32) as usize;
+    let fade_length=((TONE_FADE.as_secs_f32()*SAMPLE_RATE as f32) as usize).min(length/2).max(1);
+
+    (0..length).map(|i| {
+        let phase=(2.0*PI*frequency*i as f32/SAMPLE_RATE as f32).sin();
+        let value=match waveform {
+            Waveform::Sine => phase,
+            Waveform::Square if phase>=0.0 => 1.0,
+            Waveform::Square => -1.0,
+            };
+        let envelope=(i.min(length-1-i) as f32/fade_length as f32).min(1.0);
+
+        (value*envelope*TONE_AMPLITUDE*i16::MAX as f32) as i16
+        }).collect()
+    }
+
 #[cfg(test)]
 mod tests {
+
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
         }
+
+    #[test]
+    fn tones_are_generated() {
+        let samples=tone_samples(Waveform::Sine, 441.0, Duration::from_millis(100));
+        let peak=(TONE_AMPLITUDE*i16::MAX as f32) as i16;
+
+        assert_eq!(samples.len(), 4410);
+        assert_eq!(samples[0], 0);
+        assert_eq!(samples[4409], 0);
+        assert!(*samples.iter().max().unwrap()>=peak-1);
+
+        let samples=tone_samples(Waveform::Square, 441.0, Duration::from_millis(100));
+        assert!(samples[1001..1049].iter().all(|sample| *sample==peak));
+        assert!(samples[1051..1099].iter().all(|sample| *sample==-peak));
+        }
+
+    #[test]
+    fn silent_sounds_play() {
+        let audio=Audio::silent();
+
+        assert_eq!(audio.name(), "silent");
+        assert!(audio.load("/nonexistent/Bump.wav").is_err());
+        assert!(audio.set_volume(0.5).is_ok());
+
+        let sound=audio.generate(Waveform::Sine, 440.0, Duration::from_millis(10)).unwrap();
+        assert!(sound.play().is_ok());
+        assert!(sound.play_with(-1.0, 0.5).is_ok());
+        }
+
     }
@@ -0,0 +1,63 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU Lesser General Public License as published by
+* the Free Software Foundation, version 2.1.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU Lesser General Public License for more details.
+*
+* You should have received a copy of the GNU Lesser General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::fs;
+
+use crate::{BassError, Output, Sample};
+
+//Used where BASS is unavailable, files are still checked so missing sounds are reported
+
+pub struct SilentOutput;
+
+impl Output for SilentOutput {
+
+    fn name(&self) -> &str {
+        "silent"
+        }
+
+    fn load(&self, file_path: &str) -> Result<Box<dyn Sample>, BassError> {
+        fs::metadata(file_path).map_err(|_| BassError::FileOpen)?;
+
+        Ok(Box::new(SilentSample))
+        }
+
+    fn load_from_memory(&self, data: &[u8]) -> Result<Box<dyn Sample>, BassError> {
+        if data.is_empty() {
+            return Err(BassError::Empty);
+            }
+
+        Ok(Box::new(SilentSample))
+        }
+
+    fn create(&self, _samples: &[i16]) -> Result<Box<dyn Sample>, BassError> {
+        Ok(Box::new(SilentSample))
+        }
+
+    fn set_volume(&self, _volume: f32) -> Result<(), BassError> {
+        Ok(())
+        }
+
+    }
+
+struct SilentSample;
+
+impl Sample for SilentSample {
+
+    fn play(&self, _pan: f32, _volume: f32) -> Result<(), BassError> {
+        Ok(())
+        }
+
+    }
@@ -0,0 +1,197 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::env;
+use std::process;
+
+use ride_core::RideText;
+use ride_core::reformat::{ReformatDirection, ReformatPreset, ReformatScope};
+
+const USAGE: &str="Usage:
+    ride-cli outline FILE [--depth N]
+        Prints the lines up to the given depth, 1 by default.
+    ride-cli extract FILE PATH
+        Prints the block at the path, consisting of components separated by /.
+        A component is either the number of the line among its siblings, counted from 1,
+        or the beginning of its text.
+    ride-cli check FILE
+        Reports errors in the structure of the file.
+    ride-cli reformat FILE (--preset NAME | --begin MARK --end MARK) [--reverse]
+        Prints the file reindented according to block marks, or with marks generated from the indentation if reversed.
+        Presets: c, pascal, lua, ruby, latex.";
+
+fn main() {
+    let arguments: Vec<String>=env::args().skip(1).collect();
+
+    match run(&arguments) {
+        Ok(output) => print!("{}", output),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+            },
+        };
+    }
+
+fn run(arguments: &[String]) -> Result<String, String> {
+    let (command, file_path)=match arguments {
+        [command, file_path, ..] => (command, file_path),
+        _ => return Err(USAGE.to_string()),
+        };
+    let options=&arguments[2..];
+
+    let mut ride_text=RideText::new();
+    ride_text.load_from_file(file_path).map_err(|message| format!("{}: {}", file_path, message))?;
+
+    match &command[..] {
+        "outline" => {
+            let depth=match get_option(options, "--depth")? {
+                Some(depth) => depth.parse::<usize>().map_err(|_| format!("Invalid depth {}", depth))?,
+                None => 1,
+                };
+
+            Ok(outline(&ride_text, depth))
+            },
+        "extract" => match options {
+            [path] => extract(&mut ride_text, path),
+            _ => Err(USAGE.to_string()),
+            },
+        "check" => Ok(format!("{}: {} lines, no errors found\n", file_path, ride_text.line_count())),
+        "reformat" => {
+            let preset=match (get_option(options, "--preset")?, get_option(options, "--begin")?, get_option(options, "--end")?) {
+                (Some(name), None, None) => ReformatPreset::from_name(name).ok_or_else(|| format!("Unknown preset {}", name))?,
+                (None, Some(beginning_mark), Some(ending_mark)) => ReformatPreset::custom(beginning_mark, ending_mark),
+                _ => return Err(USAGE.to_string()),
+                };
+            let direction=if options.iter().any(|i| i=="--reverse") {
+                ReformatDirection::IndentationToMarks
+                }
+            else {
+                ReformatDirection::MarksToIndentation
+                };
+
+            ride_text.reformat(&preset, direction, ReformatScope::Document)?;
+
+            Ok(ride_text.render())
+            },
+        _ => Err(USAGE.to_string()),
+        }
+    }
+
+fn outline(ride_text: &RideText, depth: usize) -> String {
+    let mut result=String::new();
+
+    for (indentation_level, text) in ride_text.lines(0, ride_text.line_count()) {
+        if indentation_level<depth && !text.trim().is_empty() {
+            result+=&"    ".repeat(indentation_level);
+            result+=text;
+            result.push('\n');
+            }
+        }
+
+    result
+    }
+
+fn extract(ride_text: &mut RideText, path: &str) -> Result<String, String> {
+    let components: Vec<&str>=path.split('/').filter(|i| !i.is_empty()).collect();
+
+    if components.is_empty() {
+        return Err("The path is empty".to_string());
+        }
+
+    for (index, component) in components.iter().enumerate() {
+        if index>0 {
+            ride_text.increase_indentation_level().map_err(|_| format!("{} has no block", components[..index].join("/")))?;
+            ride_text.navigate_to_next_line()?;
+            }
+
+        find_sibling(ride_text, component).map_err(|_| format!("{} not found", components[..=index].join("/")))?;
+        }
+
+    ride_text.get_selected_text(false).map(|text| text+"\n")
+    }
+
+//Moves to the sibling of the current line given by its number or the beginning of its text
+
+fn find_sibling(ride_text: &mut RideText, component: &str) -> Result<(), String> {
+    let sibling_number=component.parse::<usize>().ok();
+    let mut current_sibling_number=1;
+
+    loop {
+        let matches=match sibling_number {
+            Some(sibling_number) => sibling_number==current_sibling_number,
+            None => ride_text.get_current_line().trim_start().starts_with(component),
+            };
+
+        if matches {
+            return Ok(());
+            }
+
+        ride_text.navigate_to_next_line()?;
+        current_sibling_number+=1;
+        }
+    }
+
+fn get_option<'a>(options: &'a [String], name: &str) -> Result<Option<&'a str>, String> {
+    match options.iter().position(|i| i==name) {
+        Some(index) => match options.get(index+1) {
+            Some(value) => Ok(Some(value)),
+            None => Err(format!("Missing value of {}", name)),
+            },
+        None => Ok(None),
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DOCUMENT: &str="class Foo\n    def a\n        pass\n    def b\n        return 1\nclass Bar\n    x=1\n";
+
+    fn load(text: &str) -> RideText {
+        let mut ride_text=RideText::new();
+        ride_text.load(text.to_string()).unwrap();
+
+        ride_text
+        }
+
+    #[test]
+    fn outline_is_limited_by_depth() {
+        let ride_text=load(DOCUMENT);
+
+        assert_eq!(outline(&ride_text, 1), "class Foo\nclass Bar\n");
+        assert_eq!(outline(&ride_text, 2), "class Foo\n    def a\n    def b\nclass Bar\n    x=1\n");
+        }
+
+    #[test]
+    fn blocks_are_extracted_by_path() {
+        assert_eq!(extract(&mut load(DOCUMENT), "class Foo/def b").unwrap(), "    def b\n        return 1\n");
+        assert_eq!(extract(&mut load(DOCUMENT), "1/2/1").unwrap(), "        return 1\n");
+        assert_eq!(extract(&mut load(DOCUMENT), "2").unwrap(), "class Bar\n    x=1\n");
+        assert_eq!(extract(&mut load(DOCUMENT), "class Foo/def c").unwrap_err(), "class Foo/def c not found");
+        assert_eq!(extract(&mut load(DOCUMENT), "2/1/1").unwrap_err(), "2/1 has no block");
+        }
+
+    #[test]
+    fn options_are_read() {
+        let options=vec!["--begin".to_string(), "{".to_string(), "--end".to_string()];
+
+        assert_eq!(get_option(&options, "--begin"), Ok(Some("{")));
+        assert_eq!(get_option(&options, "--preset"), Ok(None));
+        assert!(get_option(&options, "--end").is_err());
+        }
+
+    }
@@ -0,0 +1,506 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//Every object member and array element becomes a line, objects and arrays are opened by a line ending with { or [,
+//their content forms the line's subblock. Closing brackets and commas are generated when rendering.
+
+use super::super::Line;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub indentation: String,
+    pub compact: bool,
+    }
+
+impl Style {
+
+    pub fn new() -> Style {
+        Style {indentation: "    ".to_string(), compact: false}
+        }
+
+    //Minified documents are saved minified, others with the indentation of their first indented line
+
+    pub fn detect(text: &str) -> Style {
+        let text=text.trim();
+
+        if !text.contains('\n') && text.len()>2 {
+            return Style {compact: true, ..Style::new()};
+            }
+
+        for line in text.lines() {
+            let indentation: String=line.chars().take_while(|c| *c==' ' || *c=='\t').collect();
+
+            if !indentation.is_empty() && indentation.len()<line.len() {
+                return Style {indentation, compact: false};
+                }
+            }
+
+        Style::new()
+        }
+
+    }
+
+enum Value {
+    Scalar(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+    }
+
+pub fn parse(text: &str) -> Result<Vec<Line>, String> {
+    let mut parser=Parser::new(text);
+    let mut lines: Vec<Line>=Vec::new();
+
+    for (key, value) in parser.parse_items()? {
+        emit_lines(key.as_deref(), &value, 0, &mut lines);
+        }
+
+    Ok(lines)
+    }
+
+pub fn render(lines: &[Line], style: &Style) -> String {
+    let result=render_pretty(lines, &style.indentation);
+
+    if style.compact {
+        if let Ok(value)=Parser::new(&result).parse_document() {
+            let mut compact=String::new();
+            write_compact(&value, &mut compact);
+            compact.push('\n');
+
+            return compact;
+            }
+        }
+
+    result
+    }
+
+pub fn validate(text: &str) -> Result<(), String> {
+    Parser::new(text).parse_document().map(|_| ())
+    }
+
+fn render_pretty(lines: &[Line], indentation: &str) -> String {
+    let lines: Vec<(usize, String)>=lines.iter()
+    .map(|i| (i.indentation_level, i.text.as_str().trim().trim_end_matches(',').trim_end().to_string()))
+    .filter(|(_, text)| !text.is_empty())
+    .collect();
+
+    //A line is followed by a comma if there's another line on the same level before the block ends
+
+    let mut followed_by_sibling=vec![false; lines.len()];
+    let mut seen_levels: Vec<bool>=Vec::new();
+    for (index, (level, _)) in lines.iter().enumerate().rev() {
+        seen_levels.resize(level+1, false);
+        followed_by_sibling[index]=seen_levels[*level];
+        seen_levels[*level]=true;
+        }
+
+    let mut result=String::new();
+    let mut open_containers: Vec<(usize, char, bool)>=Vec::new();
+
+    for (index, (level, text)) in lines.iter().enumerate() {
+        close_open_containers(&mut result, &mut open_containers, *level, indentation);
+
+        result+=&indentation.repeat(*level);
+        result+=text;
+
+        let closing_bracket=if text.ends_with('{') {
+            Some('}')
+            }
+        else if text.ends_with('[') {
+            Some(']')
+            }
+        else {
+            None
+            };
+
+        match closing_bracket {
+            Some(bracket) => open_containers.push((*level, bracket, followed_by_sibling[index])),
+            None if followed_by_sibling[index] => result.push(','),
+            None => {},
+            };
+
+        result.push('\n');
+        }
+
+    close_open_containers(&mut result, &mut open_containers, 0, indentation);
+
+    result
+    }
+
+fn close_open_containers(result: &mut String, open_containers: &mut Vec<(usize, char, bool)>, indentation_level: usize, indentation: &str) {
+    while let Some((level, bracket, comma))=open_containers.last() {
+        if *level<indentation_level {
+            break;
+            }
+
+        *result+=&indentation.repeat(*level);
+        result.push(*bracket);
+        if *comma {
+            result.push(',');
+            }
+        result.push('\n');
+
+        open_containers.pop();
+        }
+    }
+
+fn emit_lines(key: Option<&str>, value: &Value, indentation_level: usize, lines: &mut Vec<Line>) {
+    let prefix=match key {
+        Some(key) => format!("{}: ", key),
+        None => String::new(),
+        };
+
+    match value {
+        Value::Scalar(text) => lines.push(new_line(indentation_level, &(prefix+text))),
+        Value::Array(items) if items.is_empty() => lines.push(new_line(indentation_level, &(prefix+"[]"))),
+        Value::Object(members) if members.is_empty() => lines.push(new_line(indentation_level, &(prefix+"{}"))),
+        Value::Array(items) => {
+            lines.push(new_line(indentation_level, &(prefix+"[")));
+            for item in items {
+                emit_lines(None, item, indentation_level+1, lines);
+                }
+            },
+        Value::Object(members) => {
+            lines.push(new_line(indentation_level, &(prefix+"{")));
+            for (key, value) in members {
+                emit_lines(Some(key), value, indentation_level+1, lines);
+                }
+            },
+        };
+    }
+
+fn write_compact(value: &Value, result: &mut String) {
+    match value {
+        Value::Scalar(text) => result.push_str(text),
+        Value::Array(items) => {
+            result.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index>0 {
+                    result.push(',');
+                    }
+                write_compact(item, result);
+                }
+            result.push(']');
+            },
+        Value::Object(members) => {
+            result.push('{');
+            for (index, (key, value)) in members.iter().enumerate() {
+                if index>0 {
+                    result.push(',');
+                    }
+                result.push_str(key);
+                result.push(':');
+                write_compact(value, result);
+                }
+            result.push('}');
+            },
+        };
+    }
+
+fn new_line(indentation_level: usize, text: &str) -> Line {
+    Line::new(indentation_level, text)
+    }
+
+//Strings, numbers and literals are kept exactly as written, so saving doesn't change anything that wasn't edited
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    }
+
+impl<'a> Parser<'a> {
+
+    fn new(text: &'a str) -> Parser<'a> {
+        Parser {text, position: 0}
+        }
+
+    fn parse_document(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        let value=self.parse_value()?;
+        self.skip_whitespace();
+
+        if self.position<self.text.len() {
+            return Err(self.error("Unexpected content after the end of the document"));
+            }
+
+        Ok(value)
+        }
+
+    //A comma separated list of values or members, as produced by copying lines
+
+    fn parse_items(&mut self) -> Result<Vec<(Option<String>, Value)>, String> {
+        let mut items: Vec<(Option<String>, Value)>=Vec::new();
+
+        loop {
+            self.skip_whitespace();
+            if self.position>=self.text.len() {
+                break;
+                }
+
+            let value=self.parse_value()?;
+            self.skip_whitespace();
+
+            match (value, self.peek()) {
+                (Value::Scalar(key), Some(':')) if key.starts_with('"') => {
+                    self.position+=1;
+                    self.skip_whitespace();
+                    items.push((Some(key), self.parse_value()?));
+                    self.skip_whitespace();
+                    },
+                (value, _) => items.push((None, value)),
+                };
+
+            match self.peek() {
+                Some(',') => self.position+=1,
+                None => break,
+                Some(_) => return Err(self.error("Expected a comma")),
+                };
+            }
+
+        Ok(items)
+        }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') => {
+                self.position+=1;
+                let mut members: Vec<(String, Value)>=Vec::new();
+
+                self.skip_whitespace();
+                if self.peek()==Some('}') {
+                    self.position+=1;
+                    return Ok(Value::Object(members));
+                    }
+
+                loop {
+                    self.skip_whitespace();
+                    if self.peek()!=Some('"') {
+                        return Err(self.error("Expected a member name"));
+                        }
+                    let key=self.parse_string()?;
+
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    self.skip_whitespace();
+                    members.push((key, self.parse_value()?));
+                    self.skip_whitespace();
+
+                    match self.peek() {
+                        Some(',') => self.position+=1,
+                        Some('}') => {
+                            self.position+=1;
+                            return Ok(Value::Object(members));
+                            },
+                        _ => return Err(self.error("Expected a comma or }")),
+                        };
+                    }
+                },
+            Some('[') => {
+                self.position+=1;
+                let mut items: Vec<Value>=Vec::new();
+
+                self.skip_whitespace();
+                if self.peek()==Some(']') {
+                    self.position+=1;
+                    return Ok(Value::Array(items));
+                    }
+
+                loop {
+                    self.skip_whitespace();
+                    items.push(self.parse_value()?);
+                    self.skip_whitespace();
+
+                    match self.peek() {
+                        Some(',') => self.position+=1,
+                        Some(']') => {
+                            self.position+=1;
+                            return Ok(Value::Array(items));
+                            },
+                        _ => return Err(self.error("Expected a comma or ]")),
+                        };
+                    }
+                },
+            Some('"') => Ok(Value::Scalar(self.parse_string()?)),
+            Some(c) if c=='-' || c.is_ascii_digit() => Ok(Value::Scalar(self.parse_number()?)),
+            Some(_) => {
+                for literal in ["true", "false", "null"] {
+                    if self.text[self.position..].starts_with(literal) {
+                        self.position+=literal.len();
+                        return Ok(Value::Scalar(literal.to_string()));
+                        }
+                    }
+
+                Err(self.error("Unexpected character"))
+                },
+            None => Err(self.error("Unexpected end of the document")),
+            }
+        }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let beginning=self.position;
+        self.position+=1;
+
+        while let Some(character)=self.peek() {
+            self.position+=character.len_utf8();
+
+            match character {
+                '"' => return Ok(self.text[beginning..self.position].to_string()),
+                '\\' => {
+                    match self.peek() {
+                        Some('"') | Some('\\') | Some('/') | Some('b') | Some('f') | Some('n') | Some('r') | Some('t') => self.position+=1,
+                        Some('u') => {
+                            let digits=self.text[self.position+1..].chars().take(4).filter(|c| c.is_ascii_hexdigit()).count();
+                            if digits!=4 {
+                                return Err(self.error("Invalid unicode escape"));
+                                }
+                            self.position+=5;
+                            },
+                        _ => return Err(self.error("Invalid escape sequence")),
+                        };
+                    },
+                c if c<'\u{20}' => return Err(self.error("Control character in a string")),
+                _ => {},
+                };
+            }
+
+        Err(self.error("Unterminated string"))
+        }
+
+    fn parse_number(&mut self) -> Result<String, String> {
+        let beginning=self.position;
+        let bytes=self.text.as_bytes();
+        let digits=|position: &mut usize| {
+            let start=*position;
+            while *position<bytes.len() && bytes[*position].is_ascii_digit() {
+                *position+=1;
+                }
+            *position-start
+            };
+
+        if bytes[self.position]==b'-' {
+            self.position+=1;
+            }
+
+        if self.position<bytes.len() && bytes[self.position]==b'0' {
+            self.position+=1;
+            }
+        else if digits(&mut self.position)==0 {
+            return Err(self.error("Invalid number"));
+            }
+
+        if self.position<bytes.len() && bytes[self.position]==b'.' {
+            self.position+=1;
+            if digits(&mut self.position)==0 {
+                return Err(self.error("Invalid number"));
+                }
+            }
+
+        if self.position<bytes.len() && (bytes[self.position]==b'e' || bytes[self.position]==b'E') {
+            self.position+=1;
+            if self.position<bytes.len() && (bytes[self.position]==b'+' || bytes[self.position]==b'-') {
+                self.position+=1;
+                }
+            if digits(&mut self.position)==0 {
+                return Err(self.error("Invalid number"));
+                }
+            }
+
+        Ok(self.text[beginning..self.position].to_string())
+        }
+
+    fn expect(&mut self, character: char) -> Result<(), String> {
+        if self.peek()!=Some(character) {
+            return Err(self.error(&format!("Expected {}", character)));
+            }
+
+        self.position+=1;
+        Ok(())
+        }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+        }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r')=self.peek() {
+            self.position+=1;
+            }
+        }
+
+    fn error(&self, message: &str) -> String {
+        let line_number=self.text[..self.position.min(self.text.len())].matches('\n').count()+1;
+
+        format!("{} on line {}.", message, line_number)
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<(usize, String)> {
+        lines.iter().map(|i| (i.indentation_level, i.text.as_str().trim_end().to_string())).collect()
+        }
+
+    #[test]
+    fn minified_documents_become_trees() {
+        let source="{\"name\":\"ride\",\"tags\":[\"editor\",1.5e3,{}],\"nested\":{\"a\":null}}";
+        let lines=parse(source).unwrap();
+
+        assert_eq!(texts(&lines), vec![
+            (0, "{".to_string()),
+            (1, "\"name\": \"ride\"".to_string()),
+            (1, "\"tags\": [".to_string()),
+            (2, "\"editor\"".to_string()),
+            (2, "1.5e3".to_string()),
+            (2, "{}".to_string()),
+            (1, "\"nested\": {".to_string()),
+            (2, "\"a\": null".to_string()),
+            ]);
+
+        let style=Style::detect(source);
+        assert!(style.compact);
+        assert_eq!(render(&lines, &style), source.to_string()+"\n");
+        }
+
+    #[test]
+    fn pretty_documents_keep_their_indentation() {
+        let source="{\n  \"b\": 1,\n  \"a\": [\n    true,\n    false\n  ]\n}\n";
+        let style=Style::detect(source);
+
+        assert_eq!(style, Style {indentation: "  ".to_string(), compact: false});
+        assert_eq!(render(&parse(source).unwrap(), &style), source);
+        }
+
+    #[test]
+    fn invalid_documents_are_rejected() {
+        assert!(validate("{\"a\": 1,}").is_err());
+        assert!(validate("[01]").is_err());
+        assert!(validate("\"\\x\"").is_err());
+        assert!(validate("{} {}").is_err());
+        assert!(parse("\"a\": 1, \"b\": [2]").is_ok());
+        }
+
+    #[test]
+    fn only_ascii_control_characters_are_rejected_in_strings() {
+        assert!(validate("\"a\u{80}b\"").is_ok());
+        assert!(validate("[\"\u{7f}\"]").is_ok());
+        assert!(validate("\"a\tb\"").is_err());
+        assert!(validate("\"a\u{1f}b\"").is_err());
+        }
+
+    }
@@ -0,0 +1,266 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//Headings form the tree, everything up to the next heading of the same or higher rank is the subblock of a heading.
+//Inside of sections, nested list items are children of their parent items and fenced code blocks children of their opening fence.
+//Lines keep their original indentation and text, so the document is saved back exactly as it was loaded.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use super::Line;
+
+lazy_static! {
+    static ref ATX_HEADING_REGEX: Regex=Regex::new(r"^ {0,3}(#{1,6})(\s|$)").unwrap();
+    static ref SETEXT_UNDERLINE_REGEX: Regex=Regex::new(r"^ {0,3}(=+|-+)\s*$").unwrap();
+    static ref LIST_ITEM_REGEX: Regex=Regex::new(r"^\s*([-*+]|[0-9]{1,9}[.)])(\s+|$)").unwrap();
+    static ref FENCE_REGEX: Regex=Regex::new(r"^\s*(`{3,}|~{3,})").unwrap();
+    }
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub final_newline: bool,
+    }
+
+impl Style {
+
+    pub fn new() -> Style {
+        Style {final_newline: true}
+        }
+
+    pub fn detect(text: &str) -> Style {
+        Style {final_newline: text.is_empty() || text.ends_with('\n')}
+        }
+
+    }
+
+pub fn parse(text: &str) -> Result<Vec<Line>, String> {
+    let raw_lines: Vec<&str>=text.lines().collect();
+    let mut lines: Vec<Line>=Vec::new();
+
+    //Ranks of the headings the current line is nested in
+
+    let mut headings: Vec<usize>=Vec::new();
+
+    //Indentations of list items the current line is nested in
+
+    let mut list_items: Vec<usize>=Vec::new();
+
+    //The fence closing the current code block and the indentation level of its content
+
+    let mut fence: Option<(String, usize)>=None;
+    let mut adjustment_lines: Vec<bool>=Vec::new();
+
+    for (index, raw_line) in raw_lines.iter().enumerate() {
+        let indentation: String=raw_line.chars().take_while(|c| c.is_whitespace()).collect();
+        let content=&raw_line[indentation.len()..];
+        let column=indentation.chars().count();
+
+        if let Some((closing_fence, level))=&fence {
+            let level=*level;
+
+            if content.starts_with(&closing_fence[..]) && content.trim_end().chars().all(|c| c==closing_fence.chars().next().unwrap()) {
+                fence=None;
+                }
+
+            lines.push(new_line(level, content, indentation));
+            adjustment_lines.push(false);
+            continue;
+            }
+
+        if content.is_empty() {
+            lines.push(new_line(0, content, indentation));
+            adjustment_lines.push(true);
+            continue;
+            }
+
+        let heading_rank=if let Some(captures)=ATX_HEADING_REGEX.captures(raw_line) {
+            Some(captures[1].len())
+            }
+        else {
+            find_setext_underline(&raw_lines, index).map(|underline| if raw_lines[underline].trim_start().starts_with('=') { 1 } else { 2 })
+            };
+
+        let level=if let Some(rank)=heading_rank {
+            while headings.last().is_some_and(|i| *i>=rank) {
+                headings.pop();
+                }
+
+            let level=headings.len();
+            headings.push(rank);
+            list_items.clear();
+
+            level
+            }
+        else {
+            let section_level=headings.len();
+
+            if LIST_ITEM_REGEX.is_match(raw_line) {
+                while list_items.last().is_some_and(|i| *i>column) {
+                    list_items.pop();
+                    }
+                if list_items.last()!=Some(&column) {
+                    list_items.push(column);
+                    }
+
+                section_level+list_items.len()-1
+                }
+            else if column==0 && !is_setext_underline(&raw_lines, index) {
+                list_items.clear();
+
+                section_level
+                }
+            else {
+                section_level+list_items.iter().filter(|i| **i<column).count()
+                }
+            };
+
+        if let Some(captures)=FENCE_REGEX.captures(raw_line) {
+            fence=Some((captures[1].to_string(), level+1));
+            }
+
+        //Setext underlines belong to their heading
+
+        let level=if is_setext_underline(&raw_lines, index) {
+            level.max(headings.len())
+            }
+        else {
+            level
+            };
+
+        lines.push(new_line(level, content, indentation));
+        adjustment_lines.push(false);
+        }
+
+    //Blank lines take the indentation level of the following line
+
+    let mut next_indentation_level=0;
+    for (line, adjustment) in lines.iter_mut().zip(adjustment_lines).rev() {
+        if adjustment {
+            line.indentation_level=next_indentation_level;
+            }
+
+        next_indentation_level=line.indentation_level;
+        }
+
+    Ok(lines)
+    }
+
+pub fn render(lines: &[Line], style: &Style) -> String {
+    let mut result=super::render_with_original_indentation(lines, 0, &get_child_column);
+
+    if !style.final_newline {
+        result.pop();
+        }
+
+    result
+    }
+
+fn get_child_column(parent_column: usize, parent_text: &str, _text: &str) -> usize {
+    match LIST_ITEM_REGEX.find(parent_text) {
+        Some(marker) => parent_column+marker.end(),
+        None if FENCE_REGEX.is_match(parent_text) => parent_column,
+        None => 0,
+        }
+    }
+
+fn is_previous_line_text(raw_lines: &[&str], index: usize) -> bool {
+    index>0 && !raw_lines[index-1].trim().is_empty() && !ATX_HEADING_REGEX.is_match(raw_lines[index-1])
+    }
+
+//The underline turning the paragraph starting at the given line into a setext heading, the paragraph may span several lines
+
+fn find_setext_underline(raw_lines: &[&str], index: usize) -> Option<usize> {
+    let is_interruption=|line: &str| line.trim().is_empty() || LIST_ITEM_REGEX.is_match(line) || ATX_HEADING_REGEX.is_match(line) || FENCE_REGEX.is_match(line);
+
+    if SETEXT_UNDERLINE_REGEX.is_match(raw_lines[index]) || is_interruption(raw_lines[index]) || is_previous_line_text(raw_lines, index) {
+        return None;
+        }
+
+    for (underline, line) in raw_lines.iter().enumerate().skip(index+1) {
+        if SETEXT_UNDERLINE_REGEX.is_match(line) {
+            return Some(underline);
+            }
+        if is_interruption(line) {
+            return None;
+            }
+        }
+
+    None
+    }
+
+fn is_setext_underline(raw_lines: &[&str], index: usize) -> bool {
+    if index==0 || !SETEXT_UNDERLINE_REGEX.is_match(raw_lines[index]) {
+        return false;
+        }
+
+    let mut beginning=index-1;
+    while is_previous_line_text(raw_lines, beginning) {
+        beginning-=1;
+        }
+
+    find_setext_underline(raw_lines, beginning)==Some(index)
+    }
+
+fn new_line(indentation_level: usize, text: &str, indentation: String) -> Line {
+    let mut line=Line::new(indentation_level, text);
+    line.indentation=Some(indentation);
+
+    line
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn levels(lines: &[Line]) -> Vec<usize> {
+        lines.iter().map(|i| i.indentation_level).collect()
+        }
+
+    const DOCUMENT: &str="# Ride\n\nIntroduction  \nwith a hard break.\n\n## Installation\n\n- Dependencies\n    * Rust\n    * GTK\n\n  Needed for the window.\n- Build\n\n```sh\n# not a heading\ncargo build\n```\n\nUsage\nand examples\n-----\n\n# License\nGPL";
+
+    #[test]
+    fn headings_and_lists_form_the_tree() {
+        let lines=parse(DOCUMENT).unwrap();
+
+        assert_eq!(levels(&lines), vec![
+            0, 1, 1, 1, 1,
+            1, 2,
+            2, 3, 3, 3, 3, 2, 2,
+            2, 3, 3, 3, 1,
+            1, 2, 2, 0,
+            0, 1,
+            ]);
+        }
+
+    #[test]
+    fn documents_are_saved_exactly() {
+        let lines=parse(DOCUMENT).unwrap();
+
+        assert_eq!(render(&lines, &Style::detect(DOCUMENT)), DOCUMENT);
+        }
+
+    #[test]
+    fn new_list_items_are_indented_under_their_parent() {
+        let mut lines=parse("- One\n    - Two\n").unwrap();
+        lines.push(Line::new(1, "- Three"));
+        lines.push(Line::new(2, "- Four"));
+
+        assert_eq!(render(&lines, &Style::new()), "- One\n    - Two\n    - Three\n      - Four\n");
+        }
+
+    }
@@ -0,0 +1,197 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+mod json;
+mod markdown;
+mod xml;
+mod yaml;
+
+use std::path::Path;
+
+use super::{RideText, Line};
+
+pub(super) use xml::Markup;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Format {
+    Plain,
+    Xml,
+    Html,
+    Json(json::Style),
+    Yaml(yaml::Style),
+    Markdown(markdown::Style),
+    }
+
+impl Format {
+
+    pub fn from_file_path(file_path: &str) -> Format {
+        let extension=match Path::new(file_path).extension() {
+            Some(extension) => extension.to_string_lossy().to_lowercase(),
+            None => return Format::Plain,
+            };
+
+        match &extension[..] {
+            "xml" | "xsd" | "xsl" | "xslt" | "xhtml" | "svg" | "xaml" | "plist" | "rss" | "atom" | "csproj" | "vbproj" | "fsproj" | "props" | "targets" => Format::Xml,
+            "html" | "htm" => Format::Html,
+            "json" | "geojson" | "webmanifest" => Format::Json(json::Style::new()),
+            "yaml" | "yml" => Format::Yaml(yaml::Style::new()),
+            "md" | "markdown" | "mdown" | "mkd" => Format::Markdown(markdown::Style::new()),
+            _ => Format::Plain,
+            }
+        }
+
+    //Called once per loaded file, lets the format remember the style of the original document
+
+    pub(super) fn detect_style(&mut self, text: &str) {
+        match self {
+            Format::Json(style) => *style=json::Style::detect(text),
+            Format::Yaml(style) => *style=yaml::Style::detect(text),
+            Format::Markdown(style) => *style=markdown::Style::detect(text),
+            _ => {},
+            };
+        }
+
+    //Switches between compact and pretty output, returns the new state or None if the format doesn't support it
+
+    pub(super) fn toggle_compact(&mut self) -> Option<bool> {
+        match self {
+            Format::Json(style) => {
+                style.compact^=true;
+                Some(style.compact)
+                },
+            _ => None,
+            }
+        }
+
+    pub(super) fn parse(&self, text: &str) -> Result<Vec<Line>, String> {
+        match self {
+            Format::Plain => {
+                let lines: Vec<Line>=text.lines().map(|i| Line::new(0, i)).collect();
+
+                RideText::parse_indentation(&lines)
+                },
+            Format::Xml => xml::parse(text, false),
+            Format::Html => xml::parse(text, true),
+            Format::Json(_) => json::parse(text),
+            Format::Yaml(_) => yaml::parse(text),
+            Format::Markdown(_) => markdown::parse(text),
+            }
+        }
+
+    pub(super) fn render(&self, lines: &[Line]) -> String {
+        match self {
+            Format::Plain => render_plain(lines),
+            Format::Xml => xml::render(lines, false),
+            Format::Html => xml::render(lines, true),
+            Format::Json(style) => json::render(lines, style),
+            Format::Yaml(style) => yaml::render(lines, style),
+            Format::Markdown(style) => markdown::render(lines, style),
+            }
+        }
+
+    pub(super) fn validate(&self, text: &str) -> Result<(), String> {
+        match self {
+            Format::Xml => xml::parse(text, false).map(|_| ()),
+            Format::Json(_) => json::validate(text),
+            Format::Yaml(_) => yaml::validate(text),
+            _ => Ok(()),
+            }
+        }
+
+    }
+
+fn render_plain(lines: &[Line]) -> String {
+    let mut result="".to_string();
+
+    for l in lines.iter() {
+        let line=indentation(l)+l.text.as_str().trim()+"\n";
+        result+=&line;
+        }
+
+    result
+    }
+
+fn indentation(line: &Line) -> String {
+    if line.text.is_empty() {
+        return String::new();
+        }
+
+    "    ".repeat(line.indentation_level)
+    }
+
+//Lines keep their offset against the parent, if both of them come from the original document.
+//Others take the indentation of their previous sibling, or the one given by the format for the first child of a line.
+
+fn render_with_original_indentation(lines: &[Line], indentation: usize, get_child_column: &dyn Fn(usize, &str, &str) -> usize) -> String {
+    let mut result=String::new();
+
+    //Indentation level, rendered column, original indentation and text of the lines the current one may be nested in
+
+    let mut ancestors: Vec<(usize, usize, Option<&String>, String)>=Vec::new();
+
+    for line in lines {
+        let text=line.text.as_str();
+
+        if text.trim().is_empty() {
+            if let Some(indentation)=&line.indentation {
+                result+=indentation;
+                }
+            result+=text;
+            result.push('\n');
+            continue;
+            }
+
+        let mut previous_sibling_column=None;
+        while let Some((level, column, _, _))=ancestors.last() {
+            if *level<line.indentation_level {
+                break;
+                }
+            if *level==line.indentation_level {
+                previous_sibling_column=Some(*column);
+                }
+
+            ancestors.pop();
+            }
+
+        let parent=ancestors.last();
+
+        let shift=match (parent, &line.indentation) {
+            (Some((_, column, Some(original_indentation), _)), Some(_)) => Some(*column as isize-original_indentation.chars().count() as isize),
+            (None, Some(_)) => Some(0),
+            _ => None,
+            };
+
+        let column=match (shift, &line.indentation) {
+            (Some(shift), Some(original_indentation)) => (original_indentation.chars().count() as isize+shift).max(0) as usize,
+            _ => match (previous_sibling_column, parent) {
+                (Some(column), _) => column,
+                (None, Some((_, column, _, parent_text))) => get_child_column(*column, parent_text, text),
+                (None, None) => line.indentation_level*indentation,
+                },
+            };
+
+        match (shift, &line.indentation) {
+            (Some(0), Some(original_indentation)) => result+=original_indentation,
+            _ => result+=&" ".repeat(column),
+            };
+        result+=text;
+        result.push('\n');
+
+        ancestors.push((line.indentation_level, column, line.indentation.as_ref(), text.to_string()));
+        }
+
+    result
+    }
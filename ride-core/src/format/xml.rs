@@ -0,0 +1,619 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//Every element becomes a line with its start tag, the content of the element forms its subblock.
+//Closing tags are not present in the tree at all, they're generated from it when rendering.
+//Whitespace preserving content, like preformatted text or CDATA sections, is kept as it is, including its closing tag.
+
+use super::super::{RideText, Line};
+
+const VOID_ELEMENTS: [&str; 14]=["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr"];
+const RAW_TEXT_ELEMENTS: [&str; 2]=["script", "style"];
+const PRESERVED_ELEMENTS: [&str; 2]=["pre", "textarea"];
+const SELF_CLOSING_SIBLINGS: [&str; 8]=["li", "p", "dt", "dd", "option", "tr", "td", "th"];
+
+enum Token {
+    Start {name: String, raw: String, self_closing: bool},
+    End(String),
+    Text(String),
+    RawText(String),
+    Preserved(String),
+    Markup(String),
+    }
+
+enum Node {
+    Element {name: String, start: String, children: Vec<Node>, empty: bool},
+    Text(String),
+    RawText(String),
+    Preserved(String),
+    Markup(String),
+    }
+
+//The elements a line opened when it was parsed and the text it had then
+//Opaque lines introduce content which is not markup, like the body of a script, a comment or preformatted text
+
+#[derive(Clone, Debug)]
+pub struct Markup {
+    text: String,
+    opened_elements: Vec<String>,
+    opaque: bool,
+    }
+
+pub fn parse(text: &str, html: bool) -> Result<Vec<Line>, String> {
+    let tokens=tokenize(text, html)?;
+
+    //Element name, start tag, children and the line the element started on
+
+    let mut stack: Vec<(String, String, Vec<Node>, usize)>=Vec::new();
+    let mut nodes: Vec<Node>=Vec::new();
+
+    for (line_number, token) in tokens {
+        match token {
+            Token::Start {name, raw, self_closing} => {
+                if html {
+                    if let Some((top_name, _, _, _))=stack.last() {
+                        if top_name.eq_ignore_ascii_case(&name) && SELF_CLOSING_SIBLINGS.contains(&&name.to_lowercase()[..]) {
+                            close_element(&mut stack, &mut nodes);
+                            }
+                        }
+                    }
+
+                if self_closing || (html && is_void_element(&name)) {
+                    push_node(&mut stack, &mut nodes, Node::Element {name, start: raw, children: Vec::new(), empty: true});
+                    }
+                else {
+                    stack.push((name, raw, Vec::new(), line_number));
+                    }
+                },
+            Token::End(name) => {
+                if html {
+                    if let Some(index)=stack.iter().rposition(|(n, _, _, _)| n.eq_ignore_ascii_case(&name)) {
+                        while stack.len()>index {
+                            close_element(&mut stack, &mut nodes);
+                            }
+                        }
+                    }
+                else {
+                    match stack.last() {
+                        Some((top_name, _, _, _)) if *top_name==name => close_element(&mut stack, &mut nodes),
+                        Some((top_name, _, _, _)) => return Err(format!("Unexpected closing tag </{}> on line {}, expected </{}>.", name, line_number, top_name)),
+                        None => return Err(format!("Unexpected closing tag </{}> on line {}.", name, line_number)),
+                        };
+                    }
+                },
+            Token::Text(text) => push_node(&mut stack, &mut nodes, Node::Text(text)),
+            Token::RawText(text) => push_node(&mut stack, &mut nodes, Node::RawText(text)),
+            Token::Preserved(text) => push_node(&mut stack, &mut nodes, Node::Preserved(text)),
+            Token::Markup(text) => push_node(&mut stack, &mut nodes, Node::Markup(text)),
+            };
+        }
+
+    if !html {
+        if let Some((name, _, _, line_number))=stack.last() {
+            return Err(format!("Element <{}> opened on line {} is never closed.", name, line_number));
+            }
+        }
+
+    while !stack.is_empty() {
+        close_element(&mut stack, &mut nodes);
+        }
+
+    let mut lines: Vec<Line>=Vec::new();
+    emit_lines(&nodes, 0, &mut lines);
+
+    //Blank lines take the indentation of the following line, just as in the plain text
+
+    let mut next_indentation_level=0;
+    for line in lines.iter_mut().rev() {
+        if line.text.is_empty() {
+            line.indentation_level=next_indentation_level;
+            }
+
+        next_indentation_level=line.indentation_level;
+        }
+
+    Ok(lines)
+    }
+
+//Lines keep what they opened when parsed, only new or edited lines outside of opaque content are read as markup
+
+pub fn render(lines: &[Line], html: bool) -> String {
+    let mut result=String::new();
+    let mut open_elements: Vec<(usize, String)>=Vec::new();
+    let mut opaque_level: Option<usize>=None;
+
+    for line in lines {
+        close_open_elements(&mut result, &mut open_elements, line.indentation_level);
+
+        if opaque_level.is_some_and(|level| line.indentation_level<=level) {
+            opaque_level=None;
+            }
+
+        //Preserved content is written exactly as it was loaded
+
+        if let Some(indentation)=&line.indentation {
+            result+=indentation;
+            result+=line.text.as_str();
+            result.push('\n');
+            continue;
+            }
+
+        let text=line.text.as_str().trim_start();
+
+        if text.trim_end().is_empty() {
+            result.push('\n');
+            continue;
+            }
+
+        result+=&"    ".repeat(line.indentation_level);
+        result+=text;
+        result.push('\n');
+
+        let (opened_elements, opaque)=match &line.markup {
+            Some(markup) if markup.text==line.text.as_str() => (markup.opened_elements.clone(), markup.opaque),
+            _ if opaque_level.is_some() => (Vec::new(), false),
+            _ => {
+                let opened_elements=get_unclosed_elements(text, html);
+                let opaque=is_opaque(text, &opened_elements, html);
+
+                (opened_elements, opaque)
+                },
+            };
+
+        if opaque && opaque_level.is_none() {
+            opaque_level=Some(line.indentation_level);
+            }
+
+        for name in opened_elements {
+            open_elements.push((line.indentation_level, name));
+            }
+        }
+
+    close_open_elements(&mut result, &mut open_elements, 0);
+
+    result
+    }
+
+fn close_open_elements(result: &mut String, open_elements: &mut Vec<(usize, String)>, indentation_level: usize) {
+    while let Some((level, name))=open_elements.last() {
+        if *level<indentation_level {
+            break;
+            }
+
+        *result+=&format!("{}</{}>\n", "    ".repeat(*level), name);
+        open_elements.pop();
+        }
+    }
+
+//Returns the names of elements started but not finished on the given line, in their opening order
+
+fn get_unclosed_elements(text: &str, html: bool) -> Vec<String> {
+    let tokens=match tokenize(text, html) {
+        Ok(tokens) => tokens,
+        Err(_) => return Vec::new(),
+        };
+
+    let mut result: Vec<String>=Vec::new();
+
+    for (_, token) in tokens {
+        match token {
+            Token::Start {name, self_closing: false, ..} if !(html && is_void_element(&name)) => result.push(name),
+            Token::End(name) => {
+                if let Some(index)=result.iter().rposition(|n| n==&name || (html && n.eq_ignore_ascii_case(&name))) {
+                    result.truncate(index);
+                    }
+                },
+            _ => {},
+            };
+        }
+
+    result
+    }
+
+//Whether the content nested in a line isn't markup, the line opens a script or style element, or starts an unfinished comment or CDATA section
+
+fn is_opaque(text: &str, opened_elements: &[String], html: bool) -> bool {
+    let raw_text_element=opened_elements.last().is_some_and(|name| html && (is_raw_text_element(name) || is_preserved_element(name)));
+    let unfinished_comment=text.contains("<!--") && !text.contains("-->");
+    let unfinished_cdata=text.contains("<![CDATA[") && !text.contains("]]>");
+
+    raw_text_element || unfinished_comment || unfinished_cdata
+    }
+
+fn push_node(stack: &mut [(String, String, Vec<Node>, usize)], nodes: &mut Vec<Node>, node: Node) {
+    match stack.last_mut() {
+        Some((_, _, children, _)) => children.push(node),
+        None => nodes.push(node),
+        };
+    }
+
+fn close_element(stack: &mut Vec<(String, String, Vec<Node>, usize)>, nodes: &mut Vec<Node>) {
+    if let Some((name, start, children, _))=stack.pop() {
+        push_node(stack, nodes, Node::Element {name, start, children, empty: false});
+        }
+    }
+
+fn emit_lines(nodes: &[Node], indentation_level: usize, lines: &mut Vec<Line>) {
+    for node in nodes {
+        match node {
+            Node::Element {name, start, children, empty} => {
+                let start=if start.contains('\n') {
+                    start.split('\n').map(|i| i.trim()).filter(|i| !i.is_empty()).collect::<Vec<&str>>().join(" ")
+                    }
+                else {
+                    start.to_string()
+                    };
+
+                if *empty {
+                    lines.push(markup_line(indentation_level, &start, Vec::new(), false));
+                    continue;
+                    }
+
+                //Elements with just a short text inside are kept on a single line
+
+                let inline_text=match &children[..] {
+                    [] => Some(""),
+                    [Node::Text(text)] if !text.trim().contains('\n') => Some(text.trim()),
+                    _ => None,
+                    };
+
+                if let Some(text)=inline_text {
+                    lines.push(markup_line(indentation_level, &format!("{}{}</{}>", start, text, name), Vec::new(), false));
+                    }
+                else {
+                    //Only script and style elements have raw text
+                    let opaque=children.iter().any(|i| matches!(i, Node::RawText(_)));
+
+                    lines.push(markup_line(indentation_level, &start, vec![name.clone()], opaque));
+                    emit_lines(children, indentation_level+1, lines);
+                    }
+                },
+            Node::Text(text) => {
+                if text.trim().is_empty() {
+                    if text.matches('\n').count()>=2 {
+                        lines.push(markup_line(indentation_level, "", Vec::new(), false));
+                        }
+                    continue;
+                    }
+
+                for line in text.split('\n').map(|i| i.trim()).filter(|i| !i.is_empty()) {
+                    lines.push(markup_line(indentation_level, line, Vec::new(), false));
+                    }
+                },
+            Node::RawText(text) => {
+                let raw_lines: Vec<Line>=text.lines()
+                .skip_while(|i| i.trim().is_empty())
+                .map(|i| Line::new(0, i.trim_end()))
+                .collect();
+
+                let mut raw_lines=RideText::parse_indentation(&raw_lines)
+                .unwrap_or_else(|_| raw_lines.iter().map(|i| Line::new(0, i.text.as_str().trim())).collect());
+
+                while raw_lines.last().is_some_and(|i| i.text.is_empty()) {
+                    raw_lines.pop();
+                    }
+
+                for line in raw_lines {
+                    lines.push(markup_line(line.indentation_level+indentation_level, line.text.as_str(), Vec::new(), false));
+                    }
+                },
+            Node::Preserved(text) => {
+                let mut raw_lines=text.split('\n');
+
+                //The first line starts with the tag, the following ones keep their original indentation
+
+                if let Some(first_line)=raw_lines.next() {
+                    lines.push(markup_line(indentation_level, first_line, Vec::new(), true));
+                    }
+
+                for raw_line in raw_lines {
+                    let indentation: String=raw_line.chars().take_while(|c| c.is_whitespace()).collect();
+                    let mut line=markup_line(indentation_level+1, &raw_line[indentation.len()..], Vec::new(), false);
+                    line.indentation=Some(indentation);
+                    lines.push(line);
+                    }
+                },
+            Node::Markup(text) => {
+                let markup_lines: Vec<&str>=text.split('\n').map(|i| i.trim()).filter(|i| !i.is_empty()).collect();
+                let opaque=markup_lines.len()>1;
+
+                for (index, line) in markup_lines.into_iter().enumerate() {
+                    if index==0 {
+                        lines.push(markup_line(indentation_level, line, Vec::new(), opaque));
+                        }
+                    else {
+                        lines.push(markup_line(indentation_level+1, line, Vec::new(), false));
+                        }
+                    }
+                },
+            };
+        }
+    }
+
+fn markup_line(indentation_level: usize, text: &str, opened_elements: Vec<String>, opaque: bool) -> Line {
+    let mut line=Line::new(indentation_level, text);
+    line.markup=Some(Markup {text: text.to_string(), opened_elements, opaque});
+
+    line
+    }
+
+fn is_void_element(name: &str) -> bool {
+    VOID_ELEMENTS.contains(&&name.to_lowercase()[..])
+    }
+
+fn is_raw_text_element(name: &str) -> bool {
+    RAW_TEXT_ELEMENTS.contains(&&name.to_lowercase()[..])
+    }
+
+fn is_preserved_element(name: &str) -> bool {
+    PRESERVED_ELEMENTS.contains(&&name.to_lowercase()[..])
+    }
+
+fn tokenize(text: &str, html: bool) -> Result<Vec<(usize, Token)>, String> {
+    let mut tokens: Vec<(usize, Token)>=Vec::new();
+    let mut position=0;
+    let mut line_number=1;
+
+    while position<text.len() {
+        let rest=&text[position..];
+
+        let (token, length)=if rest.starts_with("<!--") {
+            let length=find_end(rest, 4, "-->", line_number, "comment")?;
+            (Token::Markup(rest[..length].to_string()), length)
+            }
+        else if rest.starts_with("<![CDATA[") {
+            let length=find_end(rest, 9, "]]>", line_number, "CDATA section")?;
+            (Token::Preserved(rest[..length].to_string()), length)
+            }
+        else if rest.starts_with("<?") {
+            let length=find_end(rest, 2, "?>", line_number, "processing instruction")?;
+            (Token::Markup(rest[..length].to_string()), length)
+            }
+        else if rest.starts_with("<!") {
+            let mut depth=0;
+            let mut length=None;
+
+            for (index, character) in rest.char_indices() {
+                match character {
+                    '[' => depth+=1,
+                    ']' => depth-=1,
+                    '>' if depth<=0 => {
+                        length=Some(index+1);
+                        break;
+                        },
+                    _ => {},
+                    };
+                }
+
+            let length=length.ok_or(format!("Unterminated declaration on line {}.", line_number))?;
+            (Token::Markup(rest[..length].to_string()), length)
+            }
+        else if rest.starts_with("</") {
+            let length=find_end(rest, 2, ">", line_number, "closing tag")?;
+            (Token::End(rest[2..length-1].trim().to_string()), length)
+            }
+        else if rest.starts_with('<') && rest[1..].starts_with(|c: char| c.is_alphabetic() || c=='_' || c==':') {
+            let mut quote: Option<char>=None;
+            let mut length=None;
+
+            for (index, character) in rest.char_indices() {
+                match quote {
+                    Some(q) if q==character => quote=None,
+                    Some(_) => {},
+                    None => {
+                        if character=='"' || character=='\'' {
+                            quote=Some(character);
+                            }
+                        else if character=='>' {
+                            length=Some(index+1);
+                            break;
+                            }
+                        },
+                    };
+                }
+
+            let length=length.ok_or(format!("Unterminated tag on line {}.", line_number))?;
+            let raw=rest[..length].to_string();
+            let name: String=raw[1..].chars().take_while(|c| !c.is_whitespace() && *c!='/' && *c!='>').collect();
+            let self_closing=raw[..length-1].trim_end().ends_with('/');
+            let preserved=(html && is_preserved_element(&name)) || raw.contains("xml:space=\"preserve\"") || raw.contains("xml:space='preserve'");
+
+            //Whitespace preserving elements are taken as they are up to their closing tag
+
+            if preserved && !self_closing {
+                let content_length=match find_closing_tag(&rest[length..], &name, html) {
+                    Some(content_length) => content_length,
+                    None if html => rest.len()-length,
+                    None => return Err(format!("Element <{}> opened on line {} is never closed.", name, line_number)),
+                    };
+
+                (Token::Preserved(rest[..length+content_length].to_string()), length+content_length)
+                }
+            else {
+                (Token::Start {name, raw, self_closing}, length)
+                }
+            }
+        else {
+            let length=match rest.char_indices().skip(1).find(|(_, c)| *c=='<') {
+                Some((index, _)) => index,
+                None => rest.len(),
+                };
+
+            (Token::Text(rest[..length].to_string()), length)
+            };
+
+        //The content of script and style elements is not markup, it's taken as it is up to the closing tag
+
+        let raw_text_element=match &token {
+            Token::Start {name, self_closing: false, ..} if html && is_raw_text_element(name) => Some(name.to_lowercase()),
+            _ => None,
+            };
+
+        tokens.push((line_number, token));
+        line_number+=rest[..length].matches('\n').count();
+        position+=length;
+
+        if let Some(name)=raw_text_element {
+            let rest=&text[position..];
+            let length=rest.to_ascii_lowercase().find(&format!("</{}", name)).unwrap_or(rest.len());
+
+            tokens.push((line_number, Token::RawText(rest[..length].to_string())));
+            line_number+=rest[..length].matches('\n').count();
+            position+=length;
+            }
+        }
+
+    Ok(tokens)
+    }
+
+//Returns the length of the text up to and including the closing tag of the given element, skipping nested elements of the same name
+
+fn find_closing_tag(text: &str, name: &str, html: bool) -> Option<usize> {
+    let (text, name)=if html {
+        (text.to_ascii_lowercase(), name.to_ascii_lowercase())
+        }
+    else {
+        (text.to_string(), name.to_string())
+        };
+
+    let is_name_end=|c: char| c=='>' || c=='/' || c.is_whitespace();
+    let mut depth=0;
+    let mut position=0;
+
+    while let Some(index)=text[position..].find('<') {
+        let tag_position=position+index;
+        let tag=&text[tag_position+1..];
+        let tag_end=tag.find('>')?;
+
+        if let Some(rest)=tag.strip_prefix('/').and_then(|i| i.strip_prefix(&name[..])) {
+            if rest.starts_with(is_name_end) {
+                if depth==0 {
+                    return Some(tag_position+tag_end+2);
+                    }
+
+                depth-=1;
+                }
+            }
+        else if let Some(rest)=tag.strip_prefix(&name[..]) {
+            if rest.starts_with(is_name_end) && !tag[..tag_end].trim_end().ends_with('/') {
+                depth+=1;
+                }
+            }
+
+        position=tag_position+1;
+        }
+
+    None
+    }
+
+fn find_end(text: &str, offset: usize, mark: &str, line_number: usize, name: &str) -> Result<usize, String> {
+    match text[offset..].find(mark) {
+        Some(index) => Ok(offset+index+mark.len()),
+        None => Err(format!("Unterminated {} on line {}.", name, line_number)),
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn texts(lines: &[Line]) -> Vec<(usize, String)> {
+        lines.iter().map(|i| (i.indentation_level, i.text.as_str().trim_end().to_string())).collect()
+        }
+
+    #[test]
+    fn closing_tags_are_hidden_and_synthesised() {
+        let source="<?xml version=\"1.0\"?>\n<project>\n    <name>Ride</name>\n    <modules>\n        <module path=\"core\"/>\n    </modules>\n</project>\n";
+        let lines=parse(source, false).unwrap();
+
+        assert_eq!(texts(&lines), vec![
+            (0, "<?xml version=\"1.0\"?>".to_string()),
+            (0, "<project>".to_string()),
+            (1, "<name>Ride</name>".to_string()),
+            (1, "<modules>".to_string()),
+            (2, "<module path=\"core\"/>".to_string()),
+            ]);
+        assert_eq!(render(&lines, false), source);
+        }
+
+    #[test]
+    fn minified_documents_are_expanded() {
+        let lines=parse("<a><b x=\"1>2\">text<c/></b></a>", false).unwrap();
+
+        assert_eq!(texts(&lines), vec![
+            (0, "<a>".to_string()),
+            (1, "<b x=\"1>2\">".to_string()),
+            (2, "text".to_string()),
+            (2, "<c/>".to_string()),
+            ]);
+        assert_eq!(render(&lines, false), "<a>\n    <b x=\"1>2\">\n        text\n        <c/>\n    </b>\n</a>\n");
+        }
+
+    #[test]
+    fn html_void_elements_and_implied_ends() {
+        let lines=parse("<ul>\n<li>One<br>two\n<li><img src=\"a.png\">\n</ul>\n<script>\nif (a) {\n    b();\n    }\n</script>", true).unwrap();
+
+        assert_eq!(texts(&lines), vec![
+            (0, "<ul>".to_string()),
+            (1, "<li>".to_string()),
+            (2, "One".to_string()),
+            (2, "<br>".to_string()),
+            (2, "two".to_string()),
+            (1, "<li>".to_string()),
+            (2, "<img src=\"a.png\">".to_string()),
+            (0, "<script>".to_string()),
+            (1, "if (a) {".to_string()),
+            (2, "b();".to_string()),
+            (2, "}".to_string()),
+            ]);
+        assert!(render(&lines, true).ends_with("    </li>\n</ul>\n<script>\n    if (a) {\n        b();\n        }\n</script>\n"));
+        }
+
+    #[test]
+    fn raw_text_comments_and_cdata_are_not_markup() {
+        let source="<div>\n    <script>\n        if (i<n && n>0) { go(); }\n    </script>\n    <!-- first <a>\n        second <b>\n        -->\n    <![CDATA[ <x>\n  <y> ]]>\n</div>\n";
+        let mut lines=parse(source, true).unwrap();
+
+        assert_eq!(render(&lines, true), source);
+        let source="<doc>\n    <!-- <a>\n        <b> -->\n    <![CDATA[<c>]]>\n</doc>\n";
+        assert_eq!(render(&parse(source, false).unwrap(), false), source);
+
+        //Lines added inside a script aren't read as markup either
+        lines.insert(3, Line::new(2, "while (a<b) {}"));
+        assert_eq!(render(&lines, true), "<div>\n    <script>\n        if (i<n && n>0) { go(); }\n        while (a<b) {}\n    </script>\n    <!-- first <a>\n        second <b>\n        -->\n    <![CDATA[ <x>\n  <y> ]]>\n</div>\n");
+        }
+
+    #[test]
+    fn preformatted_content_is_preserved() {
+        let source="<body>\n<pre>  first\n\n    second  </pre>\n<textarea>\n a\n</textarea>\n</body>\n";
+        let lines=parse(source, true).unwrap();
+
+        assert_eq!(texts(&lines)[1], (1, "<pre>  first".to_string()));
+        assert_eq!(render(&lines, true), "<body>\n    <pre>  first\n\n    second  </pre>\n    <textarea>\n a\n</textarea>\n</body>\n");
+
+        let source="<doc>\n    <code xml:space=\"preserve\">x\n  <code>y</code>\n</code>\n    <b/>\n</doc>\n";
+        assert_eq!(render(&parse(source, false).unwrap(), false), source);
+        }
+
+    #[test]
+    fn malformed_xml_is_reported() {
+        assert!(parse("<a><b></a>", false).is_err());
+        assert!(parse("<a>", false).is_err());
+        assert!(parse("<a><b></a>", true).is_ok());
+        }
+
+    }
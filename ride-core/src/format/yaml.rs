@@ -0,0 +1,287 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//The tree follows the indentation, with two exceptions. Sequence items written at the same indentation as their key
+//are still taken as its children, and the content of block scalars forms a single opaque subblock of the line introducing it.
+//Lines remember their original indentation, which is used for rendering relatively to their parent, so unchanged parts
+//of the document are saved exactly as they were.
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+
+use super::Line;
+
+lazy_static! {
+    static ref BLOCK_SCALAR_REGEX: Regex=Regex::new(r"(^|:\s+|^(-\s+)+)[|>][0-9+-]*\s*(#.*)?$").unwrap();
+    static ref COMMENT_REGEX: Regex=Regex::new(r"(^|\s+)#.*$").unwrap();
+    static ref SEQUENCE_ITEM_PREFIX_REGEX: Regex=Regex::new(r"^(-(\s+|$))+").unwrap();
+    }
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    pub indentation: usize,
+    pub compact_sequences: bool,
+    }
+
+impl Style {
+
+    pub fn new() -> Style {
+        Style {indentation: 2, compact_sequences: false}
+        }
+
+    pub fn detect(text: &str) -> Style {
+        let (_, style)=parse_with_style(text);
+
+        style
+        }
+
+    }
+
+pub fn parse(text: &str) -> Result<Vec<Line>, String> {
+    let (lines, _)=parse_with_style(text);
+
+    Ok(lines)
+    }
+
+pub fn render(lines: &[Line], style: &Style) -> String {
+    super::render_with_original_indentation(lines, style.indentation, &|column, parent_text, text| get_child_column(column, parent_text, text, style))
+    }
+
+pub fn validate(text: &str) -> Result<(), String> {
+    for document in serde_yaml::Deserializer::from_str(text) {
+        if let Err(error)=serde_yaml::Value::deserialize(document) {
+            return Err(format!("Invalid YAML: {}", error));
+            }
+        }
+
+    Ok(())
+    }
+
+fn parse_with_style(text: &str) -> (Vec<Line>, Style) {
+    let mut lines: Vec<Line>=Vec::new();
+    let mut style=Style::new();
+    let mut indentation_detected=false;
+
+    //Virtual columns are doubled indentations, so sequence items on the level of their key can get an odd one in between
+
+    let mut steps: Vec<usize>=Vec::new();
+    let mut compact_sequences: Vec<usize>=Vec::new();
+    let mut previous_line: Option<(usize, String)>=None;
+
+    //Indentation which block scalar content must exceed, indentation level of its content
+
+    let mut block_scalar: Option<(usize, usize)>=None;
+    let mut adjustment_lines: Vec<bool>=Vec::new();
+
+    for raw_line in text.lines() {
+        let indentation: String=raw_line.chars().take_while(|c| *c==' ' || *c=='\t').collect();
+        let content=raw_line[indentation.len()..].trim_end();
+        let column=indentation.len();
+
+        if let Some((minimal_indentation, level))=block_scalar {
+            if content.is_empty() || column>minimal_indentation {
+                lines.push(new_line(level, content, indentation));
+                adjustment_lines.push(false);
+                continue;
+                }
+
+            block_scalar=None;
+            }
+
+        if content.is_empty() || content.starts_with('#') {
+            lines.push(new_line(0, content, indentation));
+            adjustment_lines.push(true);
+            continue;
+            }
+
+        let item=SEQUENCE_ITEM_PREFIX_REGEX.is_match(content);
+        compact_sequences.retain(|i| *i<column || (*i==column && item));
+
+        if item && !compact_sequences.contains(&column) {
+            if let Some((previous_column, previous_content))=&previous_line {
+                if get_key_column(*previous_column, previous_content)==column && is_key_opener(previous_content) {
+                    compact_sequences.push(column);
+                    style.compact_sequences=true;
+                    }
+                }
+            }
+
+        let virtual_column=if compact_sequences.contains(&column) {
+            column*2+1
+            }
+        else {
+            column*2
+            };
+
+        while let Some(step)=steps.last() {
+            if *step<=virtual_column {
+                break;
+                }
+            steps.pop();
+            }
+        if steps.last()!=Some(&virtual_column) {
+            steps.push(virtual_column);
+            }
+        let level=steps.len()-1;
+
+        if !indentation_detected && column>0 {
+            style.indentation=column;
+            indentation_detected=true;
+            }
+
+        if BLOCK_SCALAR_REGEX.is_match(content) {
+            block_scalar=Some((get_key_column(column, content), level+1));
+            }
+
+        lines.push(new_line(level, content, indentation));
+        adjustment_lines.push(false);
+        previous_line=Some((column, content.to_string()));
+        }
+
+    //Blank lines and comments take the indentation level of the following line
+
+    let mut next_indentation_level=0;
+    for (line, adjustment) in lines.iter_mut().zip(adjustment_lines).rev() {
+        if adjustment {
+            line.indentation_level=next_indentation_level;
+            }
+
+        next_indentation_level=line.indentation_level;
+        }
+
+    (lines, style)
+    }
+
+fn get_child_column(parent_column: usize, parent_text: &str, text: &str, style: &Style) -> usize {
+    let parent_text=COMMENT_REGEX.replace(parent_text, "");
+    let item=SEQUENCE_ITEM_PREFIX_REGEX.is_match(text);
+
+    if BLOCK_SCALAR_REGEX.is_match(&parent_text) {
+        return parent_column+style.indentation;
+        }
+
+    let key_column=get_key_column(parent_column, &parent_text);
+    let inline_content=&parent_text[key_column-parent_column..];
+
+    if inline_content.is_empty() {
+        return key_column;
+        }
+
+    if is_key_opener(inline_content) {
+        if item && style.compact_sequences {
+            return key_column;
+            }
+
+        return key_column+style.indentation;
+        }
+
+    if key_column>parent_column {
+        return key_column;
+        }
+
+    parent_column+style.indentation
+    }
+
+//The column where the content of a line starts after sequence item markers
+
+fn get_key_column(column: usize, content: &str) -> usize {
+    match SEQUENCE_ITEM_PREFIX_REGEX.find(content) {
+        Some(prefix) => column+prefix.end(),
+        None => column,
+        }
+    }
+
+fn is_key_opener(content: &str) -> bool {
+    COMMENT_REGEX.replace(content, "").trim_end().ends_with(':')
+    }
+
+fn new_line(indentation_level: usize, text: &str, indentation: String) -> Line {
+    let mut line=Line::new(indentation_level, text);
+    line.indentation=Some(indentation);
+
+    line
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::super::super::RideText;
+    use super::super::Format;
+
+    fn levels(lines: &[Line]) -> Vec<usize> {
+        lines.iter().map(|i| i.indentation_level).collect()
+        }
+
+    #[test]
+    fn compact_sequences_are_nested_under_their_key() {
+        let source="containers:\n- name: web\n  ports:\n  - 80\n  - 443\n- name: db\nkind: Pod\n";
+        let lines=parse(source).unwrap();
+        let style=Style::detect(source);
+
+        assert_eq!(levels(&lines), vec![0, 1, 2, 3, 3, 1, 0]);
+        assert!(style.compact_sequences);
+        assert_eq!(render(&lines, &style), source);
+        }
+
+    #[test]
+    fn block_scalars_are_opaque() {
+        let source="steps:\n  - run: |\n      # not a comment\n      if true; then\n        echo yes\n      fi\n    name: Test\n";
+        let lines=parse(source).unwrap();
+
+        assert_eq!(levels(&lines), vec![0, 1, 2, 2, 2, 2, 2]);
+        assert_eq!(render(&lines, &Style::detect(source)), source);
+        validate(source).unwrap();
+
+        //The content is the subblock of the line introducing it
+
+        let mut ride_text=RideText::new();
+        ride_text.format=Format::Yaml(Style::new());
+        ride_text.load(source.to_string()).unwrap();
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(ride_text.current_line_number(), 2);
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(ride_text.current_line_number(), 3);
+        }
+
+    #[test]
+    fn new_lines_get_derived_indentation() {
+        let style=Style {indentation: 2, compact_sequences: true};
+        let lines=vec![
+            Line::new(0, "spec:"),
+            Line::new(1, "containers:"),
+            Line::new(2, "- name: web"),
+            Line::new(3, "image: nginx"),
+            Line::new(3, "args:"),
+            Line::new(4, "- --verbose"),
+            ];
+
+        let text=render(&lines, &style);
+        assert_eq!(text, "spec:\n  containers:\n  - name: web\n    image: nginx\n    args:\n    - --verbose\n");
+        validate(&text).unwrap();
+        }
+
+    #[test]
+    fn invalid_documents_are_rejected() {
+        assert!(validate("a: 1\n  b: 2\n").is_err());
+        assert!(validate("a: 1\n---\nb: [2\n").is_err());
+        }
+
+    }
@@ -0,0 +1,1121 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//! The text model of the Ride editor.
+//!
+//! Documents are kept as trees of lines given by their indentation, either parsed from the indentation itself
+//! or, for formats like XML, JSON, YAML and Markdown, from their syntax. [`RideText`] holds a document together
+//! with the caret, which always moves on a single indentation level of the tree.
+//!
+//! Operations which may not be possible, like moving past the last line of a block, return [`Result`]s
+//! with a message describing why the operation failed, which can be presented to the user as is.
+
+mod format;
+pub mod reformat;
+mod text;
+mod text_renderer;
+mod tree;
+
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::ops::Range;
+
+use format::Format;
+use reformat::{ReformatDirection, ReformatPreset, ReformatScope, Tokenizer};
+use text::LineText;
+use tree::LineTree;
+
+pub use text_renderer::TextRenderer;
+
+/// The direction of [`RideText::find`].
+#[derive(Clone, Copy)]
+pub enum SearchDirection {
+    Backward,
+    Forward,
+    }
+
+/// A document with a caret and an optional selection.
+///
+/// The caret is placed on a line, a character offset within it and an indentation level. Line navigation
+/// moves only between the lines of the current indentation level, entering and leaving blocks is done by
+/// changing the level.
+pub struct RideText {
+    current_line_number: usize,
+    current_character_offset: usize,
+    current_indentation_level: usize,
+    lines: LineTree,
+    file_path: Option<String>,
+    format: Format,
+    selection_mark: Option<usize>,
+    }
+
+impl RideText {
+
+    /// Creates an empty plain text document.
+    pub fn new() -> RideText {
+        RideText {current_line_number: 0, current_character_offset: 0, current_indentation_level: 0, lines: LineTree::from(vec![Line::new(0, "")]), file_path: None, format: Format::Plain, selection_mark: None}
+        }
+
+    /// Replaces the document with the given text, parsed in the current format.
+    pub fn load(&mut self, text: String) -> Result<(), String>
+        {
+        self.format.detect_style(&text);
+        let mut lines=self.format.parse(&text)?;
+
+        if lines.is_empty() {
+            lines.push(Line::new(0, ""));
+            }
+
+        self.file_path=None;
+        self.lines=LineTree::from(lines);
+        self.current_line_number=0;
+        self.current_character_offset=0;
+        self.current_indentation_level=self.lines[0].indentation_level;
+        self.selection_mark=None;
+
+        Ok(())
+        }
+
+    /// Loads a file, choosing the format by its extension.
+    pub fn load_from_file(&mut self, file_path: &str) -> Result<(), String> {
+        let mut text=String::new();
+        let mut f=match File::open(file_path) {
+            Ok(file) => file,
+            Err(message) => return Err(message.to_string()),
+            };
+
+        if let Err(message) = f.read_to_string(&mut text) {
+            return Err(message.to_string());
+            }
+
+        self.format=Format::from_file_path(file_path);
+        let result=self.load(text);
+        self.file_path=Some(file_path.to_string());
+
+        result
+        }
+
+    /// Saves the document to the file it was loaded from. Documents not coming from a file are not saved.
+    pub fn save(&self) -> Result<(), String> {
+        if let Some(file_path) = &self.file_path {
+            let text=self.render();
+            self.format.validate(&text)?;
+
+            if let Err(message) = fs::write(file_path, text) {
+                return Err(message.to_string());
+                }
+            }
+
+        Ok(())
+        }
+
+    /// Renders the whole document in its format.
+    pub fn render(&self) -> String {
+        self.render_text(0, self.lines.len())
+        }
+
+    /// Renders the lines from the beginning line up to, but not including, the ending line.
+    pub fn render_text(&self, beginning_line: usize, ending_line: usize, ) -> String {
+        self.format.render(&self.lines.iter_range(beginning_line..ending_line).cloned().collect::<Vec<Line>>())
+        }
+
+    /// The number of lines of the document.
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+        }
+
+    /// The indentation level and the text of the lines in the given range, without the indentation.
+    pub fn lines(&self, beginning_line: usize, ending_line: usize) -> impl Iterator<Item=(usize, &str)> {
+        self.lines.iter_range(beginning_line..ending_line).map(|i| (i.indentation_level, i.text.as_str()))
+        }
+
+    /// Moves to the previous line of the current level, or to the line introducing the current block.
+    pub fn navigate_to_previous_line(&mut self) -> Result<(), String>
+        {
+
+        if self.lines[self.current_line_number].indentation_level < self.current_indentation_level {
+            return Err("Beginning of the block".to_string());
+            }
+
+        if let Some(line_number)=self.lines.find_previous(self.current_line_number, self.current_indentation_level) {
+            //Suitable line found, move the cared and return the result
+
+            self.current_line_number=line_number;
+            self.current_character_offset=0;
+
+            return Ok(());
+            }
+
+        Err("Beginning of the document".to_string())
+        }
+
+    /// Moves to the next line of the current level.
+    pub fn navigate_to_next_line(&mut self) -> Result<(), String>
+        {
+        if let Some(line_number)=self.lines.find_next(self.current_line_number, self.current_indentation_level) {
+            if self.lines[line_number].indentation_level == self.current_indentation_level
+                {
+                //Suitable line found, move the caret and return the result
+
+                self.current_line_number=line_number;
+                self.current_character_offset=0;
+
+                return Ok(());
+                }
+            }
+
+        Err("End of the block".to_string())
+        }
+
+    /// Moves to the previous character, continuing at the end of the previous line.
+    pub fn navigate_to_previous_character(&mut self) -> Result<(), String>
+        {
+        if self.current_character_offset>0 {
+            self.current_character_offset-=1;
+            return Ok(());
+            }
+
+        self.navigate_to_previous_line()?;
+        self.current_character_offset=self.lines[self.current_line_number].text.len();
+
+        Ok(())
+        }
+
+    /// Moves to the next character, continuing at the beginning of the next line.
+    pub fn navigate_to_next_character(&mut self) -> Result<(), String>
+        {
+        if self.current_character_offset<self.lines[self.current_line_number].text.len() {
+            self.current_character_offset+=1;
+            return Ok(());
+            }
+
+        self.navigate_to_next_line()
+        }
+
+    /// Moves to the line introducing the current block.
+    pub fn navigate_to_area_beginning(&mut self)
+        {
+        if self.lines[self.current_line_number].indentation_level < self.current_indentation_level {
+            return;
+            }
+
+        //The introductory line of the area, or the first line of the document on the top level
+
+        let line_number=match self.current_indentation_level {
+            0 if self.lines[0].indentation_level==0 => 0,
+            0 => self.lines.find_next(0, 0).unwrap_or(self.current_line_number),
+            indentation_level => self.lines.find_previous(self.current_line_number, indentation_level-1).unwrap_or(self.current_line_number),
+            };
+
+        if line_number!=self.current_line_number {
+            self.current_line_number=line_number;
+            self.current_character_offset=0;
+            }
+        }
+
+    /// Moves to the last line of the current level in the current block.
+    pub fn navigate_to_area_ending(&mut self)
+        {
+        let area_ending=match self.current_indentation_level {
+            0 => self.lines.len(),
+            indentation_level => self.lines.find_next(self.current_line_number, indentation_level-1).unwrap_or(self.lines.len()),
+            };
+
+        if let Some(line_number)=self.lines.find_previous(area_ending, self.current_indentation_level) {
+            if line_number>self.current_line_number && self.lines[line_number].indentation_level==self.current_indentation_level {
+                self.current_line_number=line_number;
+                self.current_character_offset=0;
+                }
+            }
+        }
+
+    /// Moves to the first character of the line.
+    pub fn navigate_to_line_beginning(&mut self) {
+        self.current_character_offset=0;
+        }
+
+    /// Moves behind the last character of the line.
+    pub fn navigate_to_line_ending(&mut self) {
+        self.current_character_offset=self.lines[self.current_line_number].text.len();
+        }
+
+    /// Moves to the given character of the line, the offset behind the last character is allowed.
+    pub fn navigate_to_character(&mut self, character_offset: usize) -> Result<(), String> {
+        if character_offset>self.lines[self.current_line_number].text.len() {
+            return Err("Out of the line".to_string());
+            }
+
+        self.current_character_offset=character_offset;
+
+        Ok(())
+        }
+
+    /// Enters the block of the current line.
+    pub fn increase_indentation_level(&mut self) -> Result<(), String>
+        {
+        if self.current_indentation_level==self.lines[self.current_line_number].indentation_level && self.line_has_subblock(self.current_line_number) {
+            self.current_indentation_level+=1;
+            return Ok(());
+            }
+
+        Err("The line has no block".to_string())
+        }
+
+    /// Leaves the current block, moving to the line introducing it.
+    pub fn decrease_indentation_level(&mut self) -> Result<(), String>
+        {
+        if self.current_indentation_level==0 {
+            return Err("Top level".to_string());
+            }
+
+        self.navigate_to_area_beginning();
+
+        if self.lines[self.current_line_number].indentation_level<self.current_indentation_level {
+            self.current_indentation_level-=1;
+            return Ok(());
+            }
+
+        Err("Top level".to_string())
+        }
+
+    /// Moves to the line with the given number, counted from 1, on its indentation level.
+    /// Returns whether the indentation level has changed.
+    pub fn jump_to_line(&mut self, line_number: usize) -> Result<bool, String> {
+        if line_number==0 {
+            return Err("Line numbering starts from 1.".to_string());
+            }
+
+        if line_number>self.lines.len() {
+            return Err(format!("Line number {} is invalid, there are just {} lines.", line_number, self.lines.len()));
+            }
+
+        let current_indentation_level=self.current_indentation_level;
+        self.current_line_number=line_number-1;
+        self.current_character_offset=0;
+        self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+
+        Ok(current_indentation_level!=self.current_indentation_level)
+        }
+
+    /// Inserts a character at the caret.
+    pub fn insert(&mut self, character: char)
+        {
+        self.lines.text_mut(self.current_line_number).insert(self.current_character_offset, character);
+        self.current_character_offset+=1;
+        }
+
+    /// The text of the current line, without the indentation.
+    pub fn get_current_line(&self) -> String {
+        self.lines[self.current_line_number].text.to_string()
+        }
+
+    /// The character at the caret, a line feed at the end of the line.
+    pub fn get_current_character(&self) -> char
+        {
+        self.lines[self.current_line_number].text.char_at(self.current_character_offset)
+        }
+
+    /// Splits the line at the caret, or adds an empty line behind the current one at its end.
+    pub fn create_new_line(&mut self)
+        {
+        if self.current_character_offset==self.lines[self.current_line_number].text.len() {
+            let new_line_number=if self.current_indentation_level==self.lines[self.current_line_number].indentation_level {
+                self.get_subblock_finishing_line_number(self.current_line_number)+1
+                } else {
+                self.current_line_number+1
+                };
+            self.lines.insert(new_line_number, Line::new(self.current_indentation_level, ""));
+            self.current_line_number=new_line_number;
+            self.current_character_offset=0;
+            }
+        else
+            {
+            let new_line=Line::new(self.lines[self.current_line_number].indentation_level, self.lines.text_mut(self.current_line_number).split_to(self.current_character_offset));
+            self.lines.insert(self.current_line_number, new_line);
+
+            self.current_line_number+=1;
+            self.current_character_offset=0;
+            }
+        }
+
+    /// Moves the text behind the caret to a new block of the current line.
+    pub fn create_new_block(&mut self)
+        {
+        let new_line=Line::new(self.lines[self.current_line_number].indentation_level+1, self.lines.text_mut(self.current_line_number).split_off(self.current_character_offset));
+        self.lines.insert(self.current_line_number+1, new_line);
+        self.current_line_number+=1;
+        self.current_character_offset=0;
+        self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+        }
+
+    /// Deletes the character before the caret, joining the line with the previous one at its beginning.
+    /// Returns the deleted character.
+    pub fn delete_character(&mut self) -> Result<char, String>
+        {
+        if self.current_character_offset>0
+            {
+            let result=self.lines.text_mut(self.current_line_number).remove(self.current_character_offset-1);
+            self.current_character_offset-=1;
+
+            Ok(result)
+            }
+        else {
+            let original_line_number=self.current_line_number;
+
+            if self.line_has_subblock(self.current_line_number) {
+                return Err("Lines with a block can't be joined".to_string());
+                }
+
+            self.navigate_to_previous_line()?;
+
+            self.current_character_offset=self.lines[self.current_line_number].text.len();
+            let original_line=self.lines.remove(original_line_number).text;
+            self.lines.text_mut(self.current_line_number).push_str(original_line.as_str());
+
+            Ok('\n')
+            }
+        }
+
+    /// Deletes the current line together with its block.
+    pub fn delete(&mut self)
+        {
+        let starting_line_number=self.current_line_number;
+        let finishing_line_number=self.get_subblock_finishing_line_number(self.current_line_number);
+        let _=self.navigate_to_previous_line();
+        self.lines.splice(starting_line_number..finishing_line_number+1, Vec::new());
+
+        if self.lines.is_empty() {
+            self.lines.insert(0, Line::new(0, ""));
+            }
+        }
+
+    /// Renders the selected lines together with their blocks, removing them if cut is set.
+    /// Without a selection, the current line is taken.
+    pub fn get_selected_text(&mut self, cut: bool) -> Result<String, String> {
+        let selection_mark=match self.selection_mark {
+            Some(line) => line,
+            None => self.current_line_number,
+            };
+
+        if self.lines[selection_mark].indentation_level<self.current_indentation_level && cut {
+            return Err("Invalid selection for cutting".to_string());
+            }
+
+        let (selection_beginning, selection_ending) = if selection_mark<=self.current_line_number {
+            (selection_mark, self.get_subblock_finishing_line_number(self.current_line_number))
+            } else {
+            (self.current_line_number, self.get_subblock_finishing_line_number(selection_mark))
+            };
+
+        let result=self.render_text(selection_beginning, selection_ending+1).trim_end().to_string();
+        if cut {
+            self.current_line_number=selection_beginning;
+            let _=self.navigate_to_previous_line();
+            self.lines.splice(selection_beginning..selection_ending+1, Vec::new());
+            if self.lines.is_empty() {
+                self.lines.insert(0, Line::new(0, ""));
+                }
+            }
+
+        Ok(result)
+        }
+
+    /// Inserts text at the caret. Text of multiple lines is parsed in the format of the document
+    /// and inserted as lines behind the current one, on the current indentation level.
+    pub fn paste(&mut self, text: &str) -> Result<(), String> {
+        let text=if !text.trim_end().contains('\n') {
+            text.trim_start()
+            }
+        else {
+            text
+            };
+        if !text.contains('\n') {
+            self.lines.text_mut(self.current_line_number).insert_str(self.current_character_offset, text);
+            self.current_character_offset+=text.chars().count();
+            return Ok(());
+            }
+
+        let lines=if self.format==Format::Plain {
+            let lines: Vec<Line>=text.replace('\r', "").split('\n').map(|i| Line::new(0, i)).collect();
+            RideText::parse_indentation(&lines)?
+            }
+        else {
+            self.format.parse(text)?
+            };
+
+        let insertion_index=if self.current_indentation_level<self.lines[self.current_line_number].indentation_level {
+            self.current_line_number+1
+            } else {
+            self.get_subblock_finishing_line_number(self.current_line_number)+1
+            };
+
+        let lines: Vec<Line>=lines.into_iter().map(|mut line| {
+            if line.indentation_level==0 {
+                line.indentation=None;
+                }
+
+            line.indentation_level+=self.current_indentation_level;
+            line
+            }).collect();
+        self.lines.splice(insertion_index..insertion_index, lines);
+
+        Ok(())
+        }
+
+    /// Marks the current line as the beginning of a selection, unless a selection is already in progress.
+    pub fn start_selection(&mut self) {
+        if self.selection_mark.is_none() {
+            self.selection_mark=Some(self.current_line_number);
+            }
+        }
+
+    /// The lines covered by the selection, including the blocks of its edge lines.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let selection_mark=self.selection_mark?;
+
+        let (selection_beginning, selection_ending) = if selection_mark<=self.current_line_number {
+            (selection_mark, self.get_subblock_finishing_line_number(self.current_line_number))
+            } else {
+            (self.current_line_number, self.get_subblock_finishing_line_number(selection_mark))
+            };
+
+        Some(selection_beginning..selection_ending+1)
+        }
+
+    /// Cancels the selection.
+    pub fn cancel_selection(&mut self) {
+        if self.selection_mark.is_some() {
+            self.selection_mark=None;
+            }
+        }
+
+    fn parse_indentation(lines: &Vec<Line>) -> Result<Vec<Line>, String>
+        {
+        let mut lines: Vec<Line>=Vec::clone(lines);
+        if lines.is_empty() {
+            return Ok(lines);
+            }
+
+        let mut indentation_steps: Vec<usize>=vec![RideText::get_indentation_level(lines[0].text.as_str())];
+        let mut current_universal_indentation_level=0;
+        let mut previous_indentation_level=indentation_steps[0];
+        let comment_level=0; // Will be mutable in future, the mark is removed for now to shut up the compiler.
+        let mut lines_adjustment_data: Vec<bool>=vec![false; lines.len()];
+
+        for (line_number, line) in lines.iter_mut().enumerate() {
+            let current_indentation_level=RideText::get_indentation_level(line.text.as_str());
+            if current_indentation_level<indentation_steps[0] && !(current_indentation_level==0 && line.text.is_empty()) {
+                return Err(format!("Indentation corrupted, code must start with the smallest indentation. Problem occurred on line {}.", line_number).to_string());
+                }
+
+            let line_text=line.text.to_string();
+
+            if line_text.starts_with(' ') || line_text.starts_with('\t') {
+                line.text=LineText::from(&line_text[current_indentation_level..]);
+                }
+
+            if (current_indentation_level==0 && line.text.is_empty()) || comment_level>0 || line_text.trim().starts_with("//") || line_text.trim().starts_with('#') || line_text.trim().starts_with(';') || line_text.trim().starts_with("<<<<<<<") || line_text.trim().starts_with(">>>>>>>") || line_text.starts_with("=======") || line_text.trim().starts_with("/*") || line_text.trim().starts_with("\"\"\"") {
+                lines_adjustment_data[line_number]=true;
+                continue;
+                }
+
+            if current_indentation_level>previous_indentation_level {
+                indentation_steps.push(current_indentation_level);
+                current_universal_indentation_level+=1;
+                }
+            else if current_indentation_level<previous_indentation_level {
+                let mut found=false;
+                for i in (0..indentation_steps.len()).rev() {
+                    if current_indentation_level==indentation_steps[i] {
+                        current_universal_indentation_level=i;
+                        indentation_steps.drain(i+1..indentation_steps.len());
+                        found=true;
+                        }
+                    }
+
+                if !found {
+                    return Err(format!("Invalid indentation on line {}.", line_number));
+                    }
+                }
+
+            line.indentation_level=current_universal_indentation_level;
+            previous_indentation_level=current_indentation_level;
+            }
+
+        //Deal with the empty lines by filling them from backward
+
+        previous_indentation_level=lines[lines.len()-1].indentation_level;
+        for (line_number, line) in lines.iter_mut().enumerate().rev() {
+            if lines_adjustment_data[line_number] && previous_indentation_level>0 {
+                line.indentation_level=previous_indentation_level;
+                }
+
+            previous_indentation_level=line.indentation_level;
+            }
+
+        Ok(lines)
+        }
+
+    fn get_indentation_level(line: &str) -> usize
+        {
+        line.chars().take_while(|c| *c==' ' || *c=='\t').count()
+        }
+    fn get_subblock_finishing_line_number(&self, starting_line_number: usize) -> usize
+        {
+        match self.lines.find_next(starting_line_number, self.lines[starting_line_number].indentation_level) {
+            Some(line_number) => line_number-1,
+            None => self.lines.len()-1,
+            }
+        }
+    fn line_has_subblock(&self, line_number: usize) -> bool
+        {
+        if line_number>=self.lines.len()-1 {
+            return false;
+            }
+
+        self.lines[line_number+1].indentation_level>self.lines[line_number].indentation_level
+        }
+
+    //Searches forward from the starting position, or backward for occurrences beginning before it
+
+    fn search_on_line(&self, line_number: usize, starting_position: usize, search_term: &str, search_direction: SearchDirection) -> Option<usize> {
+        let text=&self.lines[line_number].text;
+
+        match search_direction {
+            SearchDirection::Backward => text.rfind(search_term, (starting_position+search_term.chars().count()).saturating_sub(1)),
+            SearchDirection::Forward => text.find(search_term, starting_position),
+            }
+        }
+
+    /// Moves the caret to the next or previous occurrence of the search term, on the indentation level of its line.
+    pub fn find(&mut self, search_term: &str, search_direction: SearchDirection) -> Result<(), String> {
+
+        match search_direction {
+            SearchDirection::Backward => {
+                if let Some(position) = self.search_on_line(self.current_line_number, self.current_character_offset, search_term, SearchDirection::Backward) {
+                    self.current_character_offset=position;
+                    return Ok(());
+                    }
+
+                for line_number in (0..self.current_line_number).rev() {
+                    let character_offset=self.lines[line_number].text.len();
+                    if let Some(position) = self.search_on_line(line_number, character_offset, search_term, SearchDirection::Backward) {
+                        self.current_line_number=line_number;
+                        self.current_character_offset=position;
+                        self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+                        return Ok(());
+                        }
+                    }
+
+                },
+            SearchDirection::Forward => {
+                if let Some(position) = self.search_on_line(self.current_line_number, self.current_character_offset+1, search_term, SearchDirection::Forward) {
+                    self.current_character_offset=position;
+                    return Ok(());
+                    }
+
+                for line_number in (self.current_line_number+1)..self.lines.len() {
+                    let character_offset: usize=0;
+
+                    if let Some(position) = self.search_on_line(line_number, character_offset, search_term, SearchDirection::Forward) {
+                        self.current_line_number=line_number;
+                        self.current_character_offset=position;
+                        self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+                        return Ok(());
+                        }
+                    }
+
+                },
+            };
+
+        Err("Not found".to_string())
+        }
+
+    /// Like find, but continues from the other end of the document when the search term isn't found until its end or beginning.
+    /// Returns whether the search wrapped.
+    pub fn find_wrapping(&mut self, search_term: &str, search_direction: SearchDirection) -> Result<bool, String> {
+        if self.find(search_term, search_direction).is_ok() {
+            return Ok(false);
+            }
+
+        let line_numbers: Vec<usize>=match search_direction {
+            SearchDirection::Backward => (self.current_line_number..self.lines.len()).rev().collect(),
+            SearchDirection::Forward => (0..=self.current_line_number).collect(),
+            };
+
+        for line_number in line_numbers {
+            let character_offset=match search_direction {
+                SearchDirection::Backward => self.lines[line_number].text.len(),
+                SearchDirection::Forward => 0,
+                };
+
+            if let Some(position) = self.search_on_line(line_number, character_offset, search_term, search_direction) {
+                self.current_line_number=line_number;
+                self.current_character_offset=position;
+                self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+                return Ok(true);
+                }
+            }
+
+        Err("Not found".to_string())
+        }
+
+    /// Reindents lines according to the block marks of the preset, or generates the marks from the indentation.
+    /// Returns the numbers of the changed lines, counted from 0.
+    pub fn reformat(&mut self, preset: &ReformatPreset, direction: ReformatDirection, scope: ReformatScope) -> Result<Vec<usize>, String> {
+        let (beginning, ending)=match scope {
+            ReformatScope::Document => (0, self.lines.len()-1),
+            ReformatScope::SelectionOrBlock => match self.selection_mark {
+                Some(selection_mark) if selection_mark<=self.current_line_number => (selection_mark, self.get_subblock_finishing_line_number(self.current_line_number)),
+                Some(selection_mark) => (self.current_line_number, self.get_subblock_finishing_line_number(selection_mark)),
+                None => (self.current_line_number, self.get_subblock_finishing_line_number(self.current_line_number)),
+                },
+            };
+
+        let changed_lines=match direction {
+            ReformatDirection::MarksToIndentation => self.indent_by_marks(preset, beginning, ending),
+            ReformatDirection::IndentationToMarks => self.generate_marks(preset, beginning, ending)?,
+            };
+
+        self.current_line_number=self.current_line_number.min(self.lines.len()-1);
+        self.current_character_offset=0;
+        self.current_indentation_level=self.lines[self.current_line_number].indentation_level;
+
+        Ok(changed_lines)
+        }
+
+    fn indent_by_marks(&mut self, preset: &ReformatPreset, beginning: usize, ending: usize) -> Vec<usize> {
+        let base_indentation_level=self.lines[beginning].indentation_level as i32;
+        let mut tokenizer=Tokenizer::new(preset);
+        let mut indentation_level: i32=0;
+        let mut changed_lines: Vec<usize>=Vec::new();
+
+        for line_number in beginning..=ending {
+            let line=&self.lines[line_number];
+            let line_text=line.text.as_str().trim();
+            let marks=tokenizer.line_marks(line_text);
+
+            //Lines consisting just of beginning marks belong to the block they open, lines closing a block and opening another one, like } else {, to the parent
+
+            let line_indentation_level=if marks.only_begins {
+                indentation_level+marks.delta()
+                }
+            else if marks.leading_ends>0 && marks.begins>0 {
+                indentation_level-marks.leading_ends as i32
+                }
+            else {
+                indentation_level
+                };
+            indentation_level+=marks.delta();
+
+            let new_indentation_level=(base_indentation_level+line_indentation_level).max(0) as usize;
+            let new_text=LineText::from(line_text);
+
+            if new_indentation_level!=line.indentation_level || new_text!=line.text {
+                self.lines.update(line_number, |line| {
+                    line.indentation_level=new_indentation_level;
+                    line.text=new_text;
+                    line.indentation=None;
+                    });
+                changed_lines.push(line_number);
+                }
+            }
+
+        changed_lines
+        }
+
+    fn generate_marks(&mut self, preset: &ReformatPreset, beginning: usize, ending: usize) -> Result<Vec<usize>, String> {
+        if !preset.can_generate_marks() {
+            return Err(format!("The {} preset can't generate marks from the indentation", preset.name()));
+            }
+
+        let (beginning_mark, ending_mark)=preset.generated_marks();
+        let mut lines: Vec<Line>=Vec::new();
+        let mut changed_lines: Vec<usize>=Vec::new();
+
+        //Indentation levels of the blocks waiting for their ending mark
+
+        let mut open_blocks: Vec<usize>=Vec::new();
+
+        for line_number in beginning..=ending {
+            let mut line=self.lines[line_number].clone();
+
+            while open_blocks.last().is_some_and(|i| *i>line.indentation_level) {
+                changed_lines.push(beginning+lines.len());
+                lines.push(Line::new(open_blocks.pop().unwrap(), ending_mark));
+                }
+
+            if line_number<ending && self.line_has_subblock(line_number) && !self.is_block_closed(preset, line_number) {
+                let line_text=line.text.as_str().trim_end();
+
+                if let Some(beginning_mark)=beginning_mark {
+                    if Tokenizer::new(preset).line_marks(line_text).delta()<=0 {
+                        let separator=if line_text.is_empty() { "" } else { " " };
+                        line.text=LineText::from(format!("{}{}{}", line_text, separator, beginning_mark));
+                        changed_lines.push(beginning+lines.len());
+                        }
+                    }
+
+                open_blocks.push(line.indentation_level+1);
+                }
+
+            lines.push(line);
+            }
+
+        while let Some(indentation_level)=open_blocks.pop() {
+            changed_lines.push(beginning+lines.len());
+            lines.push(Line::new(indentation_level, ending_mark));
+            }
+
+        changed_lines.sort();
+        self.lines.splice(beginning..ending+1, lines);
+
+        Ok(changed_lines)
+        }
+
+    //Whether the last line on the first level of the subblock already ends the block
+
+    fn is_block_closed(&self, preset: &ReformatPreset, line_number: usize) -> bool {
+        let child_indentation_level=self.lines[line_number].indentation_level+1;
+        let last_line=self.lines.find_previous(self.get_subblock_finishing_line_number(line_number)+1, child_indentation_level)
+            .filter(|i| *i>line_number);
+
+        match last_line {
+            Some(last_line) => {
+                let marks=Tokenizer::new(preset).line_marks(self.lines[last_line].text.as_str().trim());
+
+                marks.leading_ends>0 && marks.begins==0
+                },
+            None => false,
+            }
+        }
+
+    /// The number of the line with the caret, counted from 0.
+    pub fn current_line_number(&self) -> usize {
+        self.current_line_number
+        }
+
+    /// The offset of the caret in characters.
+    pub fn current_character_offset(&self) -> usize {
+        self.current_character_offset
+        }
+
+    /// The indentation level the caret moves on.
+    pub fn current_indentation_level(&self) -> usize {
+        self.current_indentation_level
+        }
+
+    /// Whether the line with the caret introduces a block.
+    pub fn current_line_has_subblock(&self) -> bool {
+        self.line_has_subblock(self.current_line_number)
+        }
+
+    /// The lines of the block the caret moves in, without the line introducing it.
+    pub fn current_area(&self) -> Range<usize> {
+        let beginning=match self.current_indentation_level {
+            0 => 0,
+            indentation_level => self.lines.find_previous(self.current_line_number+1, indentation_level-1).map_or(0, |i| i+1),
+            };
+        let ending=match self.current_indentation_level {
+            0 => self.lines.len(),
+            indentation_level => self.lines.find_next(self.current_line_number, indentation_level-1).unwrap_or(self.lines.len()),
+            };
+
+        beginning..ending
+        }
+
+    /// The file the document was loaded from.
+    pub fn file_path(&self) -> &Option<String> {
+        &self.file_path
+        }
+
+    /// Switches between compact and pretty output of formats supporting both.
+    /// Returns whether the output is compact now.
+    pub fn toggle_compact_output(&mut self) -> Result<bool, String> {
+        self.format.toggle_compact().ok_or_else(|| "Not available for this format".to_string())
+        }
+
+    }
+
+impl Default for RideText {
+
+    fn default() -> Self {
+        RideText::new()
+        }
+
+    }
+
+#[derive(Clone, Debug)]
+struct Line {
+    indentation_level: usize,
+    text: LineText,
+    //The original leading whitespace, for formats which can't derive it from the tree alone
+    indentation: Option<String>,
+    //What a markup line opened when it was parsed, so it needn't be derived from its text again
+    markup: Option<format::Markup>,
+    }
+
+impl Line {
+
+    pub fn new(indentation_level: usize, text: impl Into<LineText>) -> Line
+        {
+        Line {indentation_level, text: text.into(), indentation: None, markup: None}
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const DOCUMENT: &str="class Foo\n    def a\n        pass\n    def b\n        pass\nclass Bar\n    x=1\n";
+
+    fn load(text: &str) -> RideText {
+        let mut ride_text=RideText::new();
+        ride_text.load(text.to_string()).unwrap();
+
+        ride_text
+        }
+
+    fn position(ride_text: &RideText) -> (usize, usize, usize) {
+        (ride_text.current_line_number(), ride_text.current_character_offset(), ride_text.current_indentation_level())
+        }
+
+    #[test]
+    fn documents_are_parsed_and_rendered() {
+        let ride_text=load(DOCUMENT);
+
+        assert_eq!(ride_text.line_count(), 7);
+        assert_eq!(ride_text.lines(0, 3).collect::<Vec<(usize, &str)>>(), vec![(0, "class Foo"), (1, "def a"), (2, "pass")]);
+        assert_eq!(ride_text.render(), DOCUMENT);
+        assert!(RideText::new().load("a\n  b\n c\n".to_string()).is_err());
+        }
+
+    #[test]
+    fn line_navigation_stays_on_its_level() {
+        let mut ride_text=load(DOCUMENT);
+
+        assert!(ride_text.navigate_to_previous_line().is_err());
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(position(&ride_text), (5, 0, 0));
+        assert!(ride_text.navigate_to_next_line().is_err());
+
+        ride_text.navigate_to_area_beginning();
+        assert_eq!(position(&ride_text), (0, 0, 0));
+        ride_text.navigate_to_area_ending();
+        assert_eq!(position(&ride_text), (5, 0, 0));
+        }
+
+    #[test]
+    fn blocks_are_entered_and_left() {
+        let mut ride_text=load(DOCUMENT);
+
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(position(&ride_text), (1, 0, 1));
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(position(&ride_text), (3, 0, 1));
+        assert!(ride_text.navigate_to_next_line().is_err());
+
+        ride_text.navigate_to_previous_line().unwrap();
+        ride_text.navigate_to_previous_line().unwrap();
+        assert_eq!(position(&ride_text), (0, 0, 1));
+        assert!(ride_text.navigate_to_previous_line().is_err());
+
+        ride_text.navigate_to_area_ending();
+        ride_text.decrease_indentation_level().unwrap();
+        assert_eq!(position(&ride_text), (0, 0, 0));
+        assert!(ride_text.decrease_indentation_level().is_err());
+
+        ride_text.jump_to_line(7).unwrap();
+        assert!(ride_text.increase_indentation_level().is_err());
+        assert!(ride_text.jump_to_line(8).is_err());
+        assert!(ride_text.jump_to_line(0).is_err());
+        }
+
+    #[test]
+    fn current_area_covers_the_block() {
+        let mut ride_text=load(DOCUMENT);
+
+        assert_eq!(ride_text.current_area(), 0..7);
+        ride_text.increase_indentation_level().unwrap();
+        assert_eq!(ride_text.current_area(), 1..5);
+        ride_text.navigate_to_next_line().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(ride_text.current_area(), 1..5);
+        ride_text.increase_indentation_level().unwrap();
+        assert_eq!(ride_text.current_area(), 4..5);
+        }
+
+    #[test]
+    fn search_wraps_around_the_document() {
+        let mut ride_text=load("foo\nbar\nx foo\n");
+
+        assert_eq!(ride_text.find_wrapping("foo", SearchDirection::Forward), Ok(false));
+        assert_eq!(position(&ride_text), (2, 2, 0));
+        assert_eq!(ride_text.find_wrapping("foo", SearchDirection::Forward), Ok(true));
+        assert_eq!(position(&ride_text), (0, 0, 0));
+        assert_eq!(ride_text.find_wrapping("foo", SearchDirection::Backward), Ok(true));
+        assert_eq!(position(&ride_text), (2, 2, 0));
+        assert!(ride_text.find_wrapping("baz", SearchDirection::Forward).is_err());
+        }
+
+    #[test]
+    fn lines_introducing_blocks_are_recognized() {
+        let mut ride_text=load(DOCUMENT);
+
+        assert!(ride_text.current_line_has_subblock());
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert!(ride_text.current_line_has_subblock());
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert!(!ride_text.current_line_has_subblock());
+
+        let ride_text=load("a\n    b\n");
+        assert!(ride_text.current_line_has_subblock());
+        assert!(!load("a\nb\n").current_line_has_subblock());
+        }
+
+    #[test]
+    fn character_navigation_crosses_lines() {
+        let mut ride_text=load("ab\ncd\n");
+
+        ride_text.navigate_to_line_ending();
+        assert_eq!(ride_text.get_current_character(), '\n');
+        ride_text.navigate_to_next_character().unwrap();
+        assert_eq!(position(&ride_text), (1, 0, 0));
+        ride_text.navigate_to_previous_character().unwrap();
+        assert_eq!(position(&ride_text), (0, 2, 0));
+        ride_text.navigate_to_line_beginning();
+        assert!(ride_text.navigate_to_previous_character().is_err());
+
+        ride_text.navigate_to_character(2).unwrap();
+        assert_eq!(position(&ride_text), (0, 2, 0));
+        assert!(ride_text.navigate_to_character(3).is_err());
+        }
+
+    #[test]
+    fn lines_and_blocks_are_created() {
+        let mut ride_text=load("if x:\n");
+
+        ride_text.navigate_to_line_ending();
+        ride_text.create_new_block();
+        "y()".chars().for_each(|i| ride_text.insert(i));
+        ride_text.create_new_line();
+        "z()".chars().for_each(|i| ride_text.insert(i));
+        ride_text.navigate_to_line_beginning();
+        ride_text.navigate_to_next_character().unwrap();
+        ride_text.create_new_line();
+
+        assert_eq!(ride_text.render(), "if x:\n    y()\n    z\n    ()\n");
+        assert_eq!(ride_text.get_current_line(), "()");
+        }
+
+    #[test]
+    fn deletion_joins_lines_and_removes_blocks() {
+        let mut ride_text=load(DOCUMENT);
+
+        assert!(ride_text.delete_character().is_err());
+        ride_text.jump_to_line(4).unwrap();
+        assert!(ride_text.delete_character().is_err());
+
+        ride_text.jump_to_line(5).unwrap();
+        ride_text.navigate_to_next_character().unwrap();
+        assert_eq!(ride_text.delete_character(), Ok('p'));
+
+        ride_text.jump_to_line(2).unwrap();
+        ride_text.delete();
+        assert_eq!(ride_text.render(), "class Foo\n    def b\n        ass\nclass Bar\n    x=1\n");
+        assert_eq!(position(&ride_text), (0, 0, 1));
+
+        ride_text.jump_to_line(5).unwrap();
+        assert_eq!(ride_text.delete_character(), Ok('\n'));
+        assert_eq!(ride_text.get_current_line(), "class Barx=1");
+        assert_eq!(position(&ride_text), (3, 9, 1));
+        }
+
+    #[test]
+    fn selections_are_copied_and_cut() {
+        let mut ride_text=load(DOCUMENT);
+
+        ride_text.increase_indentation_level().unwrap();
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(ride_text.selection(), None);
+        ride_text.start_selection();
+        ride_text.navigate_to_next_line().unwrap();
+        assert_eq!(ride_text.selection(), Some(1..5));
+        assert_eq!(ride_text.get_selected_text(false), Ok("    def a\n        pass\n    def b\n        pass".to_string()));
+
+        ride_text.cancel_selection();
+        assert_eq!(ride_text.get_selected_text(true), Ok("    def b\n        pass".to_string()));
+        assert_eq!(ride_text.render(), "class Foo\n    def a\n        pass\nclass Bar\n    x=1\n");
+
+        ride_text.jump_to_line(1).unwrap();
+        ride_text.start_selection();
+        ride_text.jump_to_line(2).unwrap();
+        assert!(ride_text.get_selected_text(true).is_err());
+        }
+
+    #[test]
+    fn pasted_lines_are_nested_on_the_current_level() {
+        let mut ride_text=load(DOCUMENT);
+
+        ride_text.jump_to_line(2).unwrap();
+        ride_text.paste("def c\n    return 1\n").unwrap();
+        ride_text.navigate_to_line_ending();
+        ride_text.paste(" (self)").unwrap();
+
+        assert_eq!(ride_text.render(), "class Foo\n    def a(self)\n        pass\n    def c\n        return 1\n\n    def b\n        pass\nclass Bar\n    x=1\n");
+        }
+
+    #[test]
+    fn search_moves_between_levels() {
+        let mut ride_text=load(DOCUMENT);
+
+        ride_text.find("pass", SearchDirection::Forward).unwrap();
+        assert_eq!(position(&ride_text), (2, 0, 2));
+        ride_text.find("pass", SearchDirection::Forward).unwrap();
+        assert_eq!(position(&ride_text), (4, 0, 2));
+        assert!(ride_text.find("pass", SearchDirection::Forward).is_err());
+        ride_text.find("class", SearchDirection::Backward).unwrap();
+        assert_eq!(position(&ride_text), (0, 0, 0));
+        }
+
+    #[test]
+    fn editing_and_search_use_character_offsets() {
+        let mut ride_text=load("príklad\n    čaká ťava\n");
+
+        ride_text.find("ťava", SearchDirection::Forward).unwrap();
+        assert_eq!(position(&ride_text), (1, 5, 1));
+        assert_eq!(ride_text.get_current_character(), 'ť');
+
+        ride_text.create_new_line();
+        ride_text.navigate_to_previous_character().unwrap();
+        assert_eq!(ride_text.delete_character(), Ok(' '));
+        assert_eq!(ride_text.render_text(0, 3), "príklad\n    čaká\n    ťava\n");
+
+        ride_text.find("rí", SearchDirection::Backward).unwrap();
+        assert_eq!(position(&ride_text), (0, 1, 0));
+        }
+
+    #[test]
+    fn compact_output_depends_on_the_format() {
+        assert!(RideText::new().toggle_compact_output().is_err());
+        }
+
+    }
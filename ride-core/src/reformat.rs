@@ -0,0 +1,643 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+/// Whether indentation is derived from block marks or the other way around.
+pub enum ReformatDirection {
+    MarksToIndentation,
+    IndentationToMarks,
+    }
+
+/// The lines affected by reformatting.
+pub enum ReformatScope {
+    Document,
+    /// The selected lines with their blocks, or the current line with its block without a selection.
+    SelectionOrBlock,
+    }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Escape {
+    Backslash,
+    Doubling,
+    None,
+    }
+
+#[derive(Clone, Debug)]
+struct StringSyntax {
+    opening: String,
+    closing: String,
+    escape: Escape,
+    multiline: bool,
+    }
+
+/// The block marks, comments and strings of a language family.
+#[derive(Clone, Debug)]
+pub struct ReformatPreset {
+    name: String,
+    begin_marks: Vec<String>,
+    end_marks: Vec<String>,
+    //Marks ending a block and beginning another one, like else
+    middle_marks: Vec<String>,
+    //Marks beginning a block only when they start the line, like Ruby's if
+    statement_marks: Vec<String>,
+    //Marks beginning a block only if the line doesn't start with a statement mark, like Ruby's do
+    tail_marks: Vec<String>,
+    //Marks beginning a block only in a type declaration not finished on the same line, like Pascal's class
+    type_marks: Vec<String>,
+    line_comments: Vec<String>,
+    block_comments: Vec<(String, String)>,
+    strings: Vec<StringSyntax>,
+    command_prefix: Option<char>,
+    case_sensitive: bool,
+    //Marks added when generating marks from the indentation
+    generated_marks: (Option<String>, String),
+    }
+
+impl ReformatPreset {
+
+    /// The names accepted by [`ReformatPreset::from_name`].
+    pub const NAMES: [&'static str; 5]=["c", "pascal", "lua", "ruby", "latex"];
+
+    /// Finds a preset by its name, case insensitively.
+    pub fn from_name(name: &str) -> Option<ReformatPreset> {
+        match &name.trim().to_lowercase()[..] {
+            "c" => Some(ReformatPreset::c_family()),
+            "pascal" => Some(ReformatPreset::pascal()),
+            "lua" => Some(ReformatPreset::lua()),
+            "ruby" => Some(ReformatPreset::ruby()),
+            "latex" => Some(ReformatPreset::latex()),
+            _ => None,
+            }
+        }
+
+    /// Braces of C, C++, C#, Java, JavaScript, Rust and similar languages.
+    pub fn c_family() -> ReformatPreset {
+        ReformatPreset {
+            name: "c".to_string(),
+            begin_marks: strings(&["{"]),
+            end_marks: strings(&["}"]),
+            middle_marks: Vec::new(),
+            statement_marks: Vec::new(),
+            tail_marks: Vec::new(),
+            type_marks: Vec::new(),
+            line_comments: strings(&["//"]),
+            block_comments: vec![("/*".to_string(), "*/".to_string())],
+            strings: vec![
+                string_syntax("@\"", "\"", Escape::Doubling, true),
+                string_syntax("\"\"\"", "\"\"\"", Escape::None, true),
+                string_syntax("\"", "\"", Escape::Backslash, false),
+                string_syntax("'", "'", Escape::Backslash, false),
+                string_syntax("`", "`", Escape::Backslash, true),
+                ],
+            command_prefix: None,
+            case_sensitive: true,
+            generated_marks: (Some("{".to_string()), "}".to_string()),
+            }
+        }
+
+    /// begin and end of Pascal, including class, object and interface declarations.
+    pub fn pascal() -> ReformatPreset {
+        ReformatPreset {
+            name: "pascal".to_string(),
+            begin_marks: strings(&["begin", "case", "record", "try", "repeat", "asm"]),
+            end_marks: strings(&["end", "until"]),
+            middle_marks: strings(&["except", "finally"]),
+            statement_marks: Vec::new(),
+            tail_marks: Vec::new(),
+            type_marks: strings(&["class", "object", "interface"]),
+            line_comments: strings(&["//"]),
+            block_comments: vec![("{".to_string(), "}".to_string()), ("(*".to_string(), "*)".to_string())],
+            strings: vec![string_syntax("'", "'", Escape::Doubling, false)],
+            command_prefix: None,
+            case_sensitive: false,
+            generated_marks: (Some("begin".to_string()), "end;".to_string()),
+            }
+        }
+
+    /// Lua blocks ended by end.
+    pub fn lua() -> ReformatPreset {
+        ReformatPreset {
+            name: "lua".to_string(),
+            begin_marks: strings(&["do", "then", "function", "repeat"]),
+            end_marks: strings(&["end", "until", "elseif"]),
+            middle_marks: strings(&["else"]),
+            statement_marks: Vec::new(),
+            tail_marks: Vec::new(),
+            type_marks: Vec::new(),
+            line_comments: strings(&["--"]),
+            block_comments: vec![("--[[".to_string(), "]]".to_string())],
+            strings: vec![
+                string_syntax("[[", "]]", Escape::None, true),
+                string_syntax("\"", "\"", Escape::Backslash, false),
+                string_syntax("'", "'", Escape::Backslash, false),
+                ],
+            command_prefix: None,
+            case_sensitive: true,
+            generated_marks: (None, "end".to_string()),
+            }
+        }
+
+    /// Ruby blocks ended by end, including do blocks and braces.
+    pub fn ruby() -> ReformatPreset {
+        ReformatPreset {
+            name: "ruby".to_string(),
+            begin_marks: strings(&["{"]),
+            end_marks: strings(&["end", "}"]),
+            middle_marks: strings(&["else", "elsif", "when", "rescue", "ensure"]),
+            statement_marks: strings(&["if", "unless", "while", "until", "for", "case", "def", "class", "module", "begin"]),
+            tail_marks: strings(&["do"]),
+            type_marks: Vec::new(),
+            line_comments: strings(&["#"]),
+            block_comments: vec![("=begin".to_string(), "=end".to_string())],
+            strings: vec![
+                string_syntax("\"", "\"", Escape::Backslash, false),
+                string_syntax("'", "'", Escape::Backslash, false),
+                ],
+            command_prefix: None,
+            case_sensitive: true,
+            generated_marks: (None, "end".to_string()),
+            }
+        }
+
+    /// LaTeX environments. Marks can't be generated for them, as the environment name is not known.
+    pub fn latex() -> ReformatPreset {
+        ReformatPreset {
+            name: "latex".to_string(),
+            begin_marks: strings(&["\\begin"]),
+            end_marks: strings(&["\\end"]),
+            middle_marks: Vec::new(),
+            statement_marks: Vec::new(),
+            tail_marks: Vec::new(),
+            type_marks: Vec::new(),
+            line_comments: strings(&["%"]),
+            block_comments: Vec::new(),
+            strings: Vec::new(),
+            command_prefix: Some('\\'),
+            case_sensitive: true,
+            generated_marks: (None, String::new()),
+            }
+        }
+
+    /// Arbitrary marks with the comments and strings of the C family.
+    pub fn custom(beginning_mark: &str, ending_mark: &str) -> ReformatPreset {
+        ReformatPreset {
+            name: "custom".to_string(),
+            begin_marks: vec![beginning_mark.to_string()],
+            end_marks: vec![ending_mark.to_string()],
+            generated_marks: (Some(beginning_mark.to_string()), ending_mark.to_string()),
+            ..ReformatPreset::c_family()
+            }
+        }
+
+    /// The name of the preset.
+    pub fn name(&self) -> &str {
+        &self.name
+        }
+
+    pub(super) fn can_generate_marks(&self) -> bool {
+        !self.generated_marks.1.is_empty()
+        }
+
+    pub(super) fn generated_marks(&self) -> (Option<&str>, &str) {
+        (self.generated_marks.0.as_deref(), &self.generated_marks.1)
+        }
+
+    fn classify(&self, text: &str) -> Option<Mark> {
+        let matches=|marks: &Vec<String>| marks.iter().any(|mark| if self.case_sensitive { mark==text } else { mark.eq_ignore_ascii_case(text) });
+
+        if matches(&self.begin_marks) {
+            Some(Mark::Begin)
+            }
+        else if matches(&self.end_marks) {
+            Some(Mark::End)
+            }
+        else if matches(&self.middle_marks) {
+            Some(Mark::Middle)
+            }
+        else if matches(&self.statement_marks) {
+            Some(Mark::Statement)
+            }
+        else if matches(&self.tail_marks) {
+            Some(Mark::Tail)
+            }
+        else if matches(&self.type_marks) {
+            Some(Mark::Type)
+            }
+        else {
+            None
+            }
+        }
+
+    fn marks(&self) -> impl Iterator<Item=&String> {
+        self.begin_marks.iter()
+        .chain(self.end_marks.iter())
+        .chain(self.middle_marks.iter())
+        .chain(self.statement_marks.iter())
+        .chain(self.tail_marks.iter())
+        .chain(self.type_marks.iter())
+        }
+
+    }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mark {
+    Begin,
+    End,
+    Middle,
+    Statement,
+    Tail,
+    Type,
+    }
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token<'a> {
+    Mark(Mark),
+    Other(&'a str),
+    }
+
+//Comment or string spanning over the end of a line
+
+enum Region {
+    Comment(String),
+    String(String, Escape),
+    }
+
+pub(super) struct Tokenizer<'a> {
+    preset: &'a ReformatPreset,
+    region: Option<Region>,
+    }
+
+//The way a line changes the indentation, computed from the marks it contains
+
+#[derive(Debug, PartialEq)]
+pub(super) struct LineMarks {
+    //Ending marks the line starts with
+    pub leading_ends: usize,
+    pub begins: usize,
+    pub ends: usize,
+    //The line consists just of beginning marks, like an opening brace on its own line
+    pub only_begins: bool,
+    }
+
+impl LineMarks {
+
+    pub fn delta(&self) -> i32 {
+        self.begins as i32-self.ends as i32
+        }
+
+    }
+
+impl<'a> Tokenizer<'a> {
+
+    pub fn new(preset: &'a ReformatPreset) -> Tokenizer<'a> {
+        Tokenizer {preset, region: None}
+        }
+
+    pub fn line_marks(&mut self, line: &str) -> LineMarks {
+        let tokens=self.tokenize(line);
+        let starts_with_statement=matches!(tokens.first(), Some(Token::Mark(Mark::Statement)) | Some(Token::Mark(Mark::Middle)));
+        let mut result=LineMarks {leading_ends: 0, begins: 0, ends: 0, only_begins: !tokens.is_empty()};
+        let mut leading=true;
+
+        for (index, token) in tokens.iter().enumerate() {
+            let (begins, ends)=match token {
+                Token::Mark(Mark::Begin) => (1, 0),
+                Token::Mark(Mark::End) => (0, 1),
+                Token::Mark(Mark::Middle) => (1, 1),
+                Token::Mark(Mark::Statement) if index==0 => (1, 0),
+                Token::Mark(Mark::Tail) if !starts_with_statement => (1, 0),
+                Token::Mark(Mark::Type) if opens_type(&tokens, index) => (1, 0),
+                _ => (0, 0),
+                };
+
+            if leading && ends>0 {
+                result.leading_ends+=1;
+                }
+            else {
+                leading=false;
+                }
+
+            if begins==0 || ends>0 {
+                result.only_begins=false;
+                }
+
+            result.begins+=begins;
+            result.ends+=ends;
+            }
+
+        result
+        }
+
+    fn tokenize<'l>(&mut self, line: &'l str) -> Vec<Token<'l>> {
+        let mut tokens: Vec<Token>=Vec::new();
+        let mut position=0;
+
+        while position<line.len() {
+            let rest=&line[position..];
+
+            match &self.region {
+                Some(Region::Comment(closing)) => {
+                    match rest.find(&closing[..]) {
+                        Some(index) => {
+                            position+=index+closing.len();
+                            self.region=None;
+                            },
+                        None => position=line.len(),
+                        };
+                    continue;
+                    },
+                Some(Region::String(closing, escape)) => {
+                    match find_string_end(rest, closing, *escape) {
+                        Some(index) => {
+                            position+=index;
+                            self.region=None;
+                            },
+                        None => position=line.len(),
+                        };
+                    continue;
+                    },
+                None => {},
+                };
+
+            let character=rest.chars().next().unwrap();
+
+            if character.is_whitespace() {
+                position+=character.len_utf8();
+                continue;
+                }
+
+            if self.preset.line_comments.iter().any(|i| rest.starts_with(&i[..])) && !self.starts_with_longer_block_comment(rest) {
+                break;
+                }
+
+            if let Some((opening, closing))=self.preset.block_comments.iter().find(|(opening, _)| rest.starts_with(&opening[..]) && !self.is_mark_at(line, position)) {
+                position+=opening.len();
+                self.region=Some(Region::Comment(closing.to_string()));
+                continue;
+                }
+
+            if let Some(syntax)=self.preset.strings.iter().find(|i| rest.starts_with(&i.opening[..])) {
+                let content=&rest[syntax.opening.len()..];
+
+                match find_string_end(content, &syntax.closing, syntax.escape) {
+                    Some(index) => {
+                        tokens.push(Token::Other(&rest[..syntax.opening.len()+index]));
+                        position+=syntax.opening.len()+index;
+                        continue;
+                        },
+                    None if syntax.multiline => {
+                        self.region=Some(Region::String(syntax.closing.to_string(), syntax.escape));
+                        tokens.push(Token::Other(rest));
+                        break;
+                        },
+
+                    //Unterminated quotes, like Rust lifetimes, are just ordinary characters
+
+                    None => {},
+                    };
+                }
+
+            if let Some(length)=self.mark_length_at(line, position) {
+                let mark=self.preset.classify(&line[position..position+length]).unwrap();
+                tokens.push(Token::Mark(mark));
+                position+=length;
+                continue;
+                }
+
+            if Some(character)==self.preset.command_prefix {
+                let length=rest.chars().skip(1).take_while(|c| c.is_alphabetic()).map(|c| c.len_utf8()).sum::<usize>().max(rest[1..].chars().next().map_or(0, |c| c.len_utf8()));
+                tokens.push(Token::Other(&rest[..1+length]));
+                position+=1+length;
+                continue;
+                }
+
+            let length=if is_identifier_character(character) {
+                rest.chars().take_while(|c| is_identifier_character(*c)).map(|c| c.len_utf8()).sum::<usize>()
+                }
+            else {
+                character.len_utf8()
+                };
+
+            tokens.push(Token::Other(&rest[..length]));
+            position+=length;
+            }
+
+        tokens
+        }
+
+    fn mark_length_at(&self, line: &str, position: usize) -> Option<usize> {
+        let rest=&line[position..];
+
+        self.preset.marks()
+        .filter(|mark| {
+            if mark.is_empty() || rest.len()<mark.len() || !rest.is_char_boundary(mark.len()) {
+                return false;
+                }
+
+            let candidate=&rest[..mark.len()];
+            let equal=if self.preset.case_sensitive { candidate==&mark[..] } else { candidate.eq_ignore_ascii_case(mark) };
+            if !equal {
+                return false;
+                }
+
+            //Word marks must not be parts of longer words
+
+            let starts_with_word=mark.chars().next().is_some_and(is_identifier_character);
+            let ends_with_word=mark.chars().last().is_some_and(is_identifier_character);
+
+            let glued_before=starts_with_word && line[..position].chars().last().is_some_and(is_identifier_character);
+            let glued_after=ends_with_word && rest[mark.len()..].chars().next().is_some_and(is_identifier_character);
+
+            !glued_before && !glued_after
+            })
+        .map(|mark| mark.len())
+        .max()
+        }
+
+    fn is_mark_at(&self, line: &str, position: usize) -> bool {
+        self.mark_length_at(line, position).is_some()
+        }
+
+    fn starts_with_longer_block_comment(&self, text: &str) -> bool {
+        self.preset.block_comments.iter().any(|(opening, _)| text.starts_with(&opening[..]))
+        }
+
+    }
+
+//A type mark opens a block when it follows =, possibly through packed, and the line isn't a forward declaration ended by ;
+
+fn opens_type(tokens: &[Token], index: usize) -> bool {
+    let preceding=tokens[..index].iter().rev().find(|token| !matches!(token, Token::Other(text) if text.eq_ignore_ascii_case("packed")));
+    let finished=tokens.last()==Some(&Token::Other(";")) && !tokens[index..].contains(&Token::Mark(Mark::End));
+
+    preceding==Some(&Token::Other("=")) && !finished
+    }
+
+fn find_string_end(text: &str, closing: &str, escape: Escape) -> Option<usize> {
+    let mut position=0;
+
+    while position<text.len() {
+        let rest=&text[position..];
+
+        if escape==Escape::Backslash && rest.starts_with('\\') {
+            position+=1+rest[1..].chars().next().map_or(0, |c| c.len_utf8());
+            continue;
+            }
+
+        if let Some(after_closing)=rest.strip_prefix(closing) {
+            if escape==Escape::Doubling && after_closing.starts_with(closing) {
+                position+=closing.len()*2;
+                continue;
+                }
+
+            return Some(position+closing.len());
+            }
+
+        position+=rest.chars().next().unwrap().len_utf8();
+        }
+
+    None
+    }
+
+fn is_identifier_character(character: char) -> bool {
+    character.is_alphanumeric() || character=='_'
+    }
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|i| i.to_string()).collect()
+    }
+
+fn string_syntax(opening: &str, closing: &str, escape: Escape, multiline: bool) -> StringSyntax {
+    StringSyntax {opening: opening.to_string(), closing: closing.to_string(), escape, multiline}
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use super::super::RideText;
+
+    fn deltas(preset: &ReformatPreset, lines: &[&str]) -> Vec<(usize, i32)> {
+        let mut tokenizer=Tokenizer::new(preset);
+
+        lines.iter().map(|i| {
+            let marks=tokenizer.line_marks(i);
+            (marks.leading_ends, marks.delta())
+            }).collect()
+        }
+
+    #[test]
+    fn strings_and_comments_are_skipped() {
+        let preset=ReformatPreset::c_family();
+
+        assert_eq!(deltas(&preset, &[
+            "if (a==\"{\\\"\") {",
+            "fn foo<'a>(x: &'a str) {",
+            "/* { */ x='}'; // {",
+            "# region {",
+            "} else {",
+            "var s=@\"{",
+            "}\"\" }\"; }",
+            ]), vec![(0, 1), (0, 1), (0, 0), (0, 1), (1, 0), (0, 0), (0, -1)]);
+        }
+
+    #[test]
+    fn word_marks_respect_word_boundaries() {
+        let preset=ReformatPreset::pascal();
+
+        assert_eq!(deltas(&preset, &[
+            "BEGIN { begin } writeln('end');",
+            "beginning:=ending;",
+            "end else begin",
+            "End.",
+            ]), vec![(0, 1), (0, 0), (1, 0), (1, -1)]);
+        }
+
+    #[test]
+    fn pascal_type_declarations_are_blocks() {
+        let preset=ReformatPreset::pascal();
+
+        assert_eq!(deltas(&preset, &[
+            "interface",
+            "type TShape=class;",
+            "TShapeClass = class of TShape;",
+            "TShape = class(TInterfacedObject, IShape)",
+            "class function Create: TShape;",
+            "end;",
+            "IShape = interface ['{8F5A1C20-0000-0000-0000-000000000000}']",
+            "end;",
+            "TPoint = packed object",
+            "end;",
+            "TNotify = procedure(Sender: TObject) of object;",
+            "TEmpty = class(TShape) end;",
+            ]), vec![(0, 0), (0, 0), (0, 0), (0, 1), (0, 0), (1, -1), (0, 1), (1, -1), (0, 1), (1, -1), (0, 0), (0, 0)]);
+        }
+
+    #[test]
+    fn statement_marks_open_blocks_only_at_line_start() {
+        let preset=ReformatPreset::ruby();
+
+        assert_eq!(deltas(&preset, &[
+            "while running do",
+            "return 1 if done # do",
+            "items.each do |item|",
+            "elsif x",
+            "end",
+            ]), vec![(0, 1), (0, 0), (0, 1), (1, 0), (1, -1)]);
+        }
+
+    #[test]
+    fn latex_commands() {
+        let preset=ReformatPreset::latex();
+
+        assert_eq!(deltas(&preset, &[
+            "\\begin{itemize} % \\end{itemize}",
+            "\\item 50\\% \\endgraf",
+            "\\end{itemize}",
+            ]), vec![(0, 1), (0, 0), (1, -1)]);
+        }
+
+    fn reformat(text: &str, preset: &ReformatPreset, direction: ReformatDirection) -> (Vec<usize>, String) {
+        let mut ride_text=RideText::new();
+        ride_text.load(text.to_string()).unwrap();
+        let changed_lines=ride_text.reformat(preset, direction, ReformatScope::Document).unwrap();
+
+        (changed_lines, ride_text.render_text(0, ride_text.lines.len()))
+        }
+
+    #[test]
+    fn marks_are_turned_into_indentation() {
+        let (changed_lines, text)=reformat("int main()\n{\nif (x) {\ny();\n} else {\nz();\n}\n}\n", &ReformatPreset::c_family(), ReformatDirection::MarksToIndentation);
+
+        assert_eq!(text, "int main()\n    {\n    if (x) {\n        y();\n    } else {\n        z();\n        }\n    }\n");
+        assert_eq!(changed_lines, vec![1, 2, 3, 4, 5, 6, 7]);
+        }
+
+    #[test]
+    fn marks_are_generated_from_indentation() {
+        let source="if (x)\n    y();\n    if (z)\n        w();\nv();\n";
+        let (changed_lines, text)=reformat(source, &ReformatPreset::c_family(), ReformatDirection::IndentationToMarks);
+
+        assert_eq!(text, "if (x) {\n    y();\n    if (z) {\n        w();\n        }\n    }\nv();\n");
+        assert_eq!(changed_lines, vec![0, 2, 4, 5]);
+
+        let (changed_lines, _)=reformat(&text, &ReformatPreset::c_family(), ReformatDirection::IndentationToMarks);
+        assert!(changed_lines.is_empty());
+
+        let (_, text)=reformat("while x do\n    y()\n", &ReformatPreset::lua(), ReformatDirection::IndentationToMarks);
+        assert_eq!(text, "while x do\n    y()\n    end\n");
+        }
+
+    }
@@ -0,0 +1,200 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//The text of a line without its line ending, stored as UTF-8. Offsets are counted in characters, as the caret moves by them.
+//Lines consisting only of ASCII characters, which are the vast majority in code, map offsets to bytes directly,
+//others find the byte by walking the characters of the line.
+
+use std::fmt;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(super) struct LineText {
+    text: String,
+    length: usize,
+    }
+
+impl LineText {
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+        }
+
+    //The number of characters
+
+    pub fn len(&self) -> usize {
+        self.length
+        }
+
+    pub fn is_empty(&self) -> bool {
+        self.length==0
+        }
+
+    //The character at the offset, the line ending at the end of the line
+
+    pub fn char_at(&self, offset: usize) -> char {
+        self.text[self.byte_offset(offset)..].chars().next().unwrap_or('\n')
+        }
+
+    pub fn insert(&mut self, offset: usize, character: char) {
+        let byte_offset=self.byte_offset(offset);
+        self.text.insert(byte_offset, character);
+        self.length+=1;
+        }
+
+    pub fn insert_str(&mut self, offset: usize, text: &str) {
+        let byte_offset=self.byte_offset(offset);
+        self.text.insert_str(byte_offset, text);
+        self.length+=text.chars().count();
+        }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.text.push_str(text);
+        self.length+=text.chars().count();
+        }
+
+    pub fn remove(&mut self, offset: usize) -> char {
+        let byte_offset=self.byte_offset(offset);
+        self.length-=1;
+
+        self.text.remove(byte_offset)
+        }
+
+    //Splits the line at the offset, keeping the beginning and returning the rest
+
+    pub fn split_off(&mut self, offset: usize) -> LineText {
+        let byte_offset=self.byte_offset(offset);
+        let rest=LineText::from(self.text.split_off(byte_offset));
+        self.length-=rest.length;
+
+        rest
+        }
+
+    //Splits the line at the offset, keeping the rest and returning the beginning
+
+    pub fn split_to(&mut self, offset: usize) -> LineText {
+        let rest=self.split_off(offset);
+
+        std::mem::replace(self, rest)
+        }
+
+    //The offset of the first occurrence of the term starting at the given offset or later
+
+    pub fn find(&self, term: &str, from: usize) -> Option<usize> {
+        if term.is_empty() || from>self.length {
+            return None;
+            }
+
+        let byte_offset=self.byte_offset(from);
+
+        self.text[byte_offset..].find(term).map(|i| self.char_offset(byte_offset+i))
+        }
+
+    //The offset of the last occurrence of the term ending before the given offset
+
+    pub fn rfind(&self, term: &str, before: usize) -> Option<usize> {
+        if term.is_empty() {
+            return None;
+            }
+
+        let byte_offset=self.byte_offset(before.min(self.length));
+
+        self.text[..byte_offset].rfind(term).map(|i| self.char_offset(i))
+        }
+
+    fn is_ascii(&self) -> bool {
+        self.text.len()==self.length
+        }
+
+    fn byte_offset(&self, offset: usize) -> usize {
+        if self.is_ascii() {
+            return offset.min(self.text.len());
+            }
+
+        self.text.char_indices().nth(offset).map_or(self.text.len(), |(i, _)| i)
+        }
+
+    fn char_offset(&self, byte_offset: usize) -> usize {
+        if self.is_ascii() {
+            return byte_offset;
+            }
+
+        self.text[..byte_offset].chars().count()
+        }
+
+    }
+
+impl From<String> for LineText {
+
+    fn from(text: String) -> LineText {
+        let length=text.chars().count();
+
+        LineText {text, length}
+        }
+
+    }
+
+impl From<&str> for LineText {
+
+    fn from(text: &str) -> LineText {
+        LineText::from(text.to_string())
+        }
+
+    }
+
+impl fmt::Display for LineText {
+
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str(&self.text)
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn offsets_are_counted_in_characters() {
+        let mut text=LineText::from("čaj a káva");
+
+        assert_eq!(text.len(), 10);
+        assert_eq!(text.char_at(1), 'a');
+        assert_eq!(text.char_at(10), '\n');
+
+        text.insert(3, '!');
+        assert_eq!(text.remove(0), 'č');
+        assert_eq!(text.as_str(), "aj! a káva");
+
+        let rest=text.split_off(6);
+        assert_eq!((text.as_str(), rest.as_str()), ("aj! a ", "káva"));
+        assert_eq!(text.split_to(2).as_str(), "aj");
+        assert_eq!(text.len(), 4);
+        }
+
+    #[test]
+    fn search_returns_character_offsets() {
+        let text=LineText::from("ťava ťava");
+
+        assert_eq!(text.find("ava", 0), Some(1));
+        assert_eq!(text.find("ava", 2), Some(6));
+        assert_eq!(text.find("ava", 10), None);
+        assert_eq!(text.rfind("ava", 9), Some(6));
+        assert_eq!(text.rfind("ava", 8), Some(1));
+        assert_eq!(text.rfind("", 8), None);
+        }
+
+    }
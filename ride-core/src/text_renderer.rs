@@ -0,0 +1,101 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+
+/// Turns text into the form it's spoken in, using user defined replacements of characters and phrases.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TextRenderer {
+    characters_definitions: HashMap<char, String>,
+    strings_definitions: HashMap<String, String>,
+    }
+
+impl TextRenderer {
+
+    /// Creates a renderer without any definitions.
+    pub fn new() -> TextRenderer {
+        TextRenderer {characters_definitions: HashMap::new(), strings_definitions: HashMap::new()}
+        }
+
+    /// Renders a line of text, replacing the defined phrases.
+    pub fn render_text(&self, text: &str) -> String {
+        if text.is_empty() || text=="\n" {
+            return "blank".to_string();
+            }
+
+        let mut local_text=text.to_string();
+
+        for k in self.strings_definitions.keys() {
+            local_text=local_text.replace(k, &self.strings_definitions[k]);
+            }
+
+        local_text
+        }
+
+    /// The definition of a single character, if there is one.
+    pub fn render_character(&self, character: char) -> Option<String> {
+        if character=='\n' {
+            return Some("blank".to_string());
+            }
+
+        if self.characters_definitions.contains_key(&character) {
+            return Some((self.characters_definitions[&character]).to_string());
+            }
+
+        None
+        }
+
+    /// Defines how a character is spoken.
+    pub fn add_character_definition(&mut self, character: char, definition: &str) {
+        self.characters_definitions.insert(character, definition.to_string());
+        }
+
+    /// Defines a replacement of a phrase.
+    pub fn add_string_definition(&mut self, string: &str, definition: &str) {
+        self.strings_definitions.insert(string.to_string(), definition.to_string());
+        }
+
+    }
+
+impl Default for TextRenderer {
+
+    fn default() -> Self {
+        TextRenderer::new()
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn definitions_are_applied() {
+        let mut text_renderer=TextRenderer::new();
+        text_renderer.add_character_definition('{', "left brace");
+        text_renderer.add_string_definition("fn ", "function ");
+
+        assert_eq!(text_renderer.render_text("fn main() {"), "function main() {");
+        assert_eq!(text_renderer.render_text(""), "blank");
+        assert_eq!(text_renderer.render_character('{'), Some("left brace".to_string()));
+        assert_eq!(text_renderer.render_character('\n'), Some("blank".to_string()));
+        assert_eq!(text_renderer.render_character('x'), None);
+        }
+
+    }
@@ -0,0 +1,512 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+//Lines are kept in a randomized balanced binary tree ordered by their position. Every node knows the size and the minimal
+//indentation level of its subtree, so finding the end of a block, the next sibling or the parent of a line, as well as inserting
+//and removing blocks of lines, takes logarithmic time instead of scanning the whole document.
+
+use std::ops::{Index, Range};
+
+use super::Line;
+use super::text::LineText;
+
+struct Node {
+    line: Line,
+    size: usize,
+    minimal_indentation_level: usize,
+    left: Option<Box<Node>>,
+    right: Option<Box<Node>>,
+    }
+
+impl Node {
+
+    fn new(line: Line) -> Box<Node> {
+        let minimal_indentation_level=line.indentation_level;
+
+        Box::new(Node {line, size: 1, minimal_indentation_level, left: None, right: None})
+        }
+
+    fn update(&mut self) {
+        self.size=1+size(&self.left)+size(&self.right);
+        self.minimal_indentation_level=self.line.indentation_level
+            .min(minimal_indentation_level(&self.left))
+            .min(minimal_indentation_level(&self.right));
+        }
+
+    }
+
+pub(super) struct LineTree {
+    root: Option<Box<Node>>,
+    seed: u64,
+    }
+
+impl LineTree {
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+        }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+        }
+
+    pub fn get(&self, index: usize) -> Option<&Line> {
+        let mut node=self.root.as_deref();
+        let mut index=index;
+
+        while let Some(current_node) = node {
+            let left_size=size(&current_node.left);
+
+            if index<left_size {
+                node=current_node.left.as_deref();
+                }
+            else if index==left_size {
+                return Some(&current_node.line);
+                }
+            else {
+                index-=left_size+1;
+                node=current_node.right.as_deref();
+                }
+            }
+
+        None
+        }
+
+    //The text can be changed freely, as it doesn't affect the structure of the tree
+
+    pub fn text_mut(&mut self, index: usize) -> &mut LineText {
+        let node=self.root.as_deref_mut().expect("Line index out of range");
+
+        &mut node_mut(node, index).line.text
+        }
+
+    pub fn update<R>(&mut self, index: usize, function: impl FnOnce(&mut Line) -> R) -> R {
+        update(self.root.as_deref_mut().expect("Line index out of range"), index, function)
+        }
+
+    pub fn insert(&mut self, index: usize, line: Line) {
+        self.splice(index..index, vec![line]);
+        }
+
+    pub fn remove(&mut self, index: usize) -> Line {
+        self.splice(index..index+1, Vec::new()).pop().expect("Line index out of range")
+        }
+
+    //Replaces the lines in the given range, returning the removed ones
+
+    pub fn splice(&mut self, range: Range<usize>, lines: Vec<Line>) -> Vec<Line> {
+        let (left, rest)=split(self.root.take(), range.start);
+        let (middle, right)=split(rest, range.end-range.start);
+
+        let mut removed_lines=Vec::with_capacity(size(&middle));
+        collect(middle, &mut removed_lines);
+
+        let inserted_lines_count=lines.len();
+        let middle=build(&mut lines.into_iter(), inserted_lines_count);
+
+        let left=self.merge(left, middle);
+        self.root=self.merge(left, right);
+
+        removed_lines
+        }
+
+    pub fn iter_range(&self, range: Range<usize>) -> LineTreeIterator<'_> {
+        let mut stack: Vec<&Node>=Vec::new();
+        let mut node=self.root.as_deref();
+        let mut index=range.start;
+
+        while let Some(current_node) = node {
+            let left_size=size(&current_node.left);
+
+            if index<left_size {
+                stack.push(current_node);
+                node=current_node.left.as_deref();
+                }
+            else if index==left_size {
+                stack.push(current_node);
+                break;
+                }
+            else {
+                index-=left_size+1;
+                node=current_node.right.as_deref();
+                }
+            }
+
+        LineTreeIterator {stack, remaining: range.end.min(self.len()).saturating_sub(range.start)}
+        }
+
+    //The first line after the given one with indentation level lower or equal to the given one
+
+    pub fn find_next(&self, line_number: usize, indentation_level: usize) -> Option<usize> {
+        find_first(&self.root, 0, line_number+1, indentation_level)
+        }
+
+    //The last line before the given one with indentation level lower or equal to the given one
+
+    pub fn find_previous(&self, line_number: usize, indentation_level: usize) -> Option<usize> {
+        find_last(&self.root, 0, line_number, indentation_level)
+        }
+
+    //Chooses the root of merged trees with probability proportional to their sizes, keeping the expected depth logarithmic
+
+    fn merge(&mut self, left: Option<Box<Node>>, right: Option<Box<Node>>) -> Option<Box<Node>> {
+        match (left, right) {
+            (None, right) => right,
+            (left, None) => left,
+            (Some(mut left), Some(mut right)) => {
+                if self.random()%((left.size+right.size) as u64)<left.size as u64 {
+                    left.right=self.merge(left.right.take(), Some(right));
+                    left.update();
+
+                    Some(left)
+                    }
+                else {
+                    right.left=self.merge(Some(left), right.left.take());
+                    right.update();
+
+                    Some(right)
+                    }
+                },
+            }
+        }
+
+    fn random(&mut self) -> u64 {
+        self.seed^=self.seed<<13;
+        self.seed^=self.seed>>7;
+        self.seed^=self.seed<<17;
+
+        self.seed
+        }
+
+    }
+
+impl From<Vec<Line>> for LineTree {
+
+    fn from(lines: Vec<Line>) -> LineTree {
+        let count=lines.len();
+
+        LineTree {root: build(&mut lines.into_iter(), count), seed: 0x2545F4914F6CDD1D}
+        }
+
+    }
+
+impl Index<usize> for LineTree {
+    type Output=Line;
+
+    fn index(&self, index: usize) -> &Line {
+        self.get(index).expect("Line index out of range")
+        }
+
+    }
+
+pub(super) struct LineTreeIterator<'a> {
+    stack: Vec<&'a Node>,
+    remaining: usize,
+    }
+
+impl<'a> Iterator for LineTreeIterator<'a> {
+    type Item=&'a Line;
+
+    fn next(&mut self) -> Option<&'a Line> {
+        if self.remaining==0 {
+            return None;
+            }
+
+        let node=self.stack.pop()?;
+        self.remaining-=1;
+
+        let mut child=node.right.as_deref();
+        while let Some(child_node) = child {
+            self.stack.push(child_node);
+            child=child_node.left.as_deref();
+            }
+
+        Some(&node.line)
+        }
+
+    }
+
+fn size(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(0, |i| i.size)
+    }
+
+fn minimal_indentation_level(node: &Option<Box<Node>>) -> usize {
+    node.as_ref().map_or(usize::MAX, |i| i.minimal_indentation_level)
+    }
+
+fn node_mut(node: &mut Node, index: usize) -> &mut Node {
+    let left_size=size(&node.left);
+
+    if index<left_size {
+        node_mut(node.left.as_deref_mut().unwrap(), index)
+        }
+    else if index==left_size {
+        node
+        }
+    else {
+        node_mut(node.right.as_deref_mut().expect("Line index out of range"), index-left_size-1)
+        }
+    }
+
+fn update<R>(node: &mut Node, index: usize, function: impl FnOnce(&mut Line) -> R) -> R {
+    let left_size=size(&node.left);
+
+    let result=if index<left_size {
+        update(node.left.as_deref_mut().unwrap(), index, function)
+        }
+    else if index==left_size {
+        function(&mut node.line)
+        }
+    else {
+        update(node.right.as_deref_mut().expect("Line index out of range"), index-left_size-1, function)
+        };
+
+    node.update();
+
+    result
+    }
+
+//Splits the tree into the first count lines and the rest
+
+fn split(node: Option<Box<Node>>, count: usize) -> (Option<Box<Node>>, Option<Box<Node>>) {
+    let mut node=match node {
+        Some(node) => node,
+        None => return (None, None),
+        };
+
+    let left_size=size(&node.left);
+
+    if count<=left_size {
+        let (left, right)=split(node.left.take(), count);
+        node.left=right;
+        node.update();
+
+        (left, Some(node))
+        }
+    else {
+        let (left, right)=split(node.right.take(), count-left_size-1);
+        node.right=left;
+        node.update();
+
+        (Some(node), right)
+        }
+    }
+
+fn build(lines: &mut impl Iterator<Item=Line>, count: usize) -> Option<Box<Node>> {
+    if count==0 {
+        return None;
+        }
+
+    let left=build(lines, count/2);
+    let mut node=Node::new(lines.next().unwrap());
+    node.left=left;
+    node.right=build(lines, count-count/2-1);
+    node.update();
+
+    Some(node)
+    }
+
+fn collect(node: Option<Box<Node>>, lines: &mut Vec<Line>) {
+    if let Some(node)=node {
+        let node=*node;
+
+        collect(node.left, lines);
+        lines.push(node.line);
+        collect(node.right, lines);
+        }
+    }
+
+fn find_first(node: &Option<Box<Node>>, offset: usize, from: usize, indentation_level: usize) -> Option<usize> {
+    let node=node.as_ref()?;
+
+    if node.minimal_indentation_level>indentation_level || offset+node.size<=from {
+        return None;
+        }
+
+    if let Some(index)=find_first(&node.left, offset, from, indentation_level) {
+        return Some(index);
+        }
+
+    let index=offset+size(&node.left);
+    if index>=from && node.line.indentation_level<=indentation_level {
+        return Some(index);
+        }
+
+    find_first(&node.right, index+1, from, indentation_level)
+    }
+
+fn find_last(node: &Option<Box<Node>>, offset: usize, before: usize, indentation_level: usize) -> Option<usize> {
+    let node=node.as_ref()?;
+
+    if node.minimal_indentation_level>indentation_level || offset>=before {
+        return None;
+        }
+
+    let index=offset+size(&node.left);
+    if let Some(index)=find_last(&node.right, index+1, before, indentation_level) {
+        return Some(index);
+        }
+
+    if index<before && node.line.indentation_level<=indentation_level {
+        return Some(index);
+        }
+
+    find_last(&node.left, offset, before, indentation_level)
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Instant;
+
+    use super::*;
+    use super::super::RideText;
+
+    fn levels(tree: &LineTree) -> Vec<usize> {
+        tree.iter_range(0..tree.len()).map(|i| i.indentation_level).collect()
+        }
+
+    #[test]
+    fn queries_match_linear_scans() {
+        let mut tree=LineTree::from(Vec::new());
+        let mut reference: Vec<usize>=Vec::new();
+        let mut seed: u64=7;
+
+        for step in 0..2000 {
+            seed=seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            let random=(seed>>33) as usize;
+            let index=random % (reference.len()+1);
+
+            if step%5==4 && !reference.is_empty() {
+                let end=(index+random%4).min(reference.len());
+                tree.splice(index..end, Vec::new());
+                reference.drain(index..end);
+                }
+            else {
+                let block: Vec<usize>=(0..random%3+1).map(|i| (random>>(i*3))%5).collect();
+                tree.splice(index..index, block.iter().map(|i| Line::new(*i, "")).collect());
+                reference.splice(index..index, block);
+                }
+            }
+
+        assert_eq!(levels(&tree), reference);
+
+        for line_number in 0..reference.len() {
+            for indentation_level in 0..5 {
+                let next=(line_number+1..reference.len()).find(|i| reference[*i]<=indentation_level);
+                let previous=(0..line_number).rev().find(|i| reference[*i]<=indentation_level);
+
+                assert_eq!(tree.find_next(line_number, indentation_level), next);
+                assert_eq!(tree.find_previous(line_number, indentation_level), previous);
+                }
+            }
+        }
+
+    #[test]
+    fn lines_are_updated_in_place() {
+        let mut tree=LineTree::from(vec![Line::new(0, "a"), Line::new(1, "b"), Line::new(1, "c")]);
+
+        tree.text_mut(1).insert(0, 'x');
+        tree.update(0, |line| line.indentation_level=2);
+
+        assert_eq!(tree[1].text.as_str(), "xb");
+        assert_eq!(tree.find_previous(2, 1), Some(1));
+        assert_eq!(tree.find_next(1, 1), Some(2));
+        assert_eq!(tree.remove(0).indentation_level, 2);
+        assert_eq!(tree.iter_range(1..5).count(), 1);
+        }
+
+    #[test]
+    fn navigation_follows_the_tree() {
+        let mut ride_text=RideText::new();
+        ride_text.load("a\n    b\n        c\n    d\n    e\n        f\ng\n".to_string()).unwrap();
+
+        ride_text.jump_to_line(4).unwrap();
+        ride_text.navigate_to_area_ending();
+        assert_eq!(ride_text.current_line_number, 4);
+        assert!(ride_text.navigate_to_next_line().is_err());
+
+        ride_text.navigate_to_area_beginning();
+        assert_eq!(ride_text.current_line_number, 0);
+        assert_eq!(ride_text.get_subblock_finishing_line_number(0), 5);
+
+        ride_text.jump_to_line(7).unwrap();
+        ride_text.navigate_to_area_beginning();
+        assert_eq!(ride_text.current_line_number, 0);
+        ride_text.navigate_to_area_ending();
+        assert_eq!(ride_text.current_line_number, 6);
+        }
+
+    //cargo test --release -- --ignored --nocapture
+
+    #[test]
+    #[ignore]
+    fn benchmark_large_files() {
+        let mut text=String::new();
+        for class in 0..2000 {
+            text+=&format!("class Class{} {{\n", class);
+            for method in 0..10 {
+                text+=&format!("    fn method{}() {{\n", method);
+                for statement in 0..4 {
+                    text+=&format!("        let value{}=compute({}, {});\n", statement, class, method);
+                    }
+                text+="        }\n";
+                }
+            text+="    }\n";
+            }
+
+        let started=Instant::now();
+        let mut ride_text=RideText::new();
+        ride_text.load(text).unwrap();
+        println!("Loading {} lines: {:?}", ride_text.lines.len(), started.elapsed());
+
+        let started=Instant::now();
+        let mut siblings=0;
+        while ride_text.navigate_to_next_line().is_ok() {
+            siblings+=1;
+            }
+        println!("Walking over {} top-level siblings: {:?}", siblings, started.elapsed());
+
+        let started=Instant::now();
+        for _ in 0..10000 {
+            ride_text.navigate_to_area_beginning();
+            ride_text.navigate_to_area_ending();
+            }
+        println!("10000 jumps to the area beginning and ending: {:?}", started.elapsed());
+
+        let started=Instant::now();
+        let line_count=ride_text.lines.len();
+        for line_number in (0..line_count).step_by(7) {
+            ride_text.get_subblock_finishing_line_number(line_number);
+            }
+        println!("{} subblock ending lookups: {:?}", line_count/7, started.elapsed());
+
+        let started=Instant::now();
+        ride_text.jump_to_line(1).unwrap();
+        for _ in 0..1000 {
+            ride_text.paste("fn pasted() {\n    body();\n    }\n").unwrap();
+            }
+        println!("1000 block pastes: {:?}", started.elapsed());
+
+        let started=Instant::now();
+        for _ in 0..1000 {
+            ride_text.jump_to_line(2).unwrap();
+            ride_text.delete();
+            }
+        println!("1000 block deletions: {:?}", started.elapsed());
+        }
+
+    }
@@ -0,0 +1,39 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::env;
+use std::path::Path;
+
+//Ride finds the BASS library next to its executable, and in BASS_LIB_DIR if it was built with one
+//The rpath is set here, since link arguments of dependencies don't reach the binary
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=BASS_LIB_DIR");
+
+    let origin=match env::var("CARGO_CFG_TARGET_OS").unwrap_or_default().as_str() {
+        //Windows looks next to the executable by default
+        "windows" => return,
+        "macos" => "@executable_path",
+        _ => "$ORIGIN",
+        };
+
+    println!("cargo:rustc-link-arg-bins=-Wl,-rpath,{}", origin);
+
+    if let Some(directory)=env::var_os("BASS_LIB_DIR") {
+        println!("cargo:rustc-link-arg-bins=-Wl,-rpath,{}", Path::new(&directory).display());
+        }
+    }
@@ -0,0 +1,167 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::ffi::c_void;
+use std::os::raw::{c_char, c_int, c_uint};
+
+use libloading::Library;
+
+use super::{BrailleCommand, BrailleDisplay};
+
+#[cfg(unix)]
+const LIBRARY_NAMES: [&str; 2]=["libbrlapi.so.0.8", "libbrlapi.so"];
+#[cfg(windows)]
+const LIBRARY_NAMES: [&str; 1]=["brlapi.dll"];
+
+#[cfg(unix)]
+type WideChar=i32;
+#[cfg(windows)]
+type WideChar=u16;
+
+//Key codes of BrlAPI commands, see brlapi_constants.h
+
+const KEY_TYPE_COMMAND: u64=0x2000_0000;
+const KEY_TYPE_MASK: u64=0xe000_0000;
+const KEY_COMMAND_BLOCK_MASK: u64=0x1fff_0000;
+const KEY_COMMAND_ARGUMENT_MASK: u64=0xffff;
+const COMMAND_LINE_UP: u64=1;
+const COMMAND_LINE_DOWN: u64=2;
+const COMMAND_PAN_LEFT: u64=23;
+const COMMAND_PAN_RIGHT: u64=24;
+const COMMAND_BLOCK_ROUTE: u64=0x1_0000;
+
+//BrlAPI is loaded when the display is opened, so Ride runs on systems without BRLTTY
+
+pub struct BrlApiDisplay {
+    library: Library,
+    size: usize,
+    }
+
+impl BrlApiDisplay {
+
+    pub fn open() -> Result<BrlApiDisplay, String> {
+        let library=LIBRARY_NAMES.iter()
+        .find_map(|name| unsafe { Library::new(name) }.ok())
+        .ok_or_else(|| "BrlAPI library not found".to_string())?;
+
+        unsafe {
+            let open_connection=library.get::<unsafe extern "C" fn(*const c_void, *mut c_void) -> c_int>(b"brlapi_openConnection\0").map_err(|error| error.to_string())?;
+            if open_connection(std::ptr::null(), std::ptr::null_mut())<0 {
+                return Err("Unable to connect to BRLTTY".to_string());
+                }
+
+            let get_display_size=library.get::<unsafe extern "C" fn(*mut c_uint, *mut c_uint) -> c_int>(b"brlapi_getDisplaySize\0").map_err(|error| error.to_string())?;
+            let (mut width, mut height)=(0, 0);
+            if get_display_size(&mut width, &mut height)<0 || width==0 {
+                return Err("Unable to get the size of the braille display".to_string());
+                }
+
+            let enter_tty_mode=library.get::<unsafe extern "C" fn(c_int, *const c_char) -> c_int>(b"brlapi_enterTtyMode\0").map_err(|error| error.to_string())?;
+            if enter_tty_mode(-1, std::ptr::null())<0 {
+                return Err("Unable to take control of the braille display".to_string());
+                }
+
+            Ok(BrlApiDisplay {library, size: width as usize})
+            }
+        }
+
+    fn command(code: u64) -> Option<BrailleCommand> {
+        if code & KEY_TYPE_MASK!=KEY_TYPE_COMMAND {
+            return None;
+            }
+
+        let argument=code & KEY_COMMAND_ARGUMENT_MASK;
+        match code & KEY_COMMAND_BLOCK_MASK {
+            0 => match argument {
+                COMMAND_LINE_UP => Some(BrailleCommand::LineUp),
+                COMMAND_LINE_DOWN => Some(BrailleCommand::LineDown),
+                COMMAND_PAN_LEFT => Some(BrailleCommand::PanLeft),
+                COMMAND_PAN_RIGHT => Some(BrailleCommand::PanRight),
+                _ => None,
+                },
+            COMMAND_BLOCK_ROUTE => Some(BrailleCommand::Route(argument as usize)),
+            _ => None,
+            }
+        }
+
+    }
+
+impl BrailleDisplay for BrlApiDisplay {
+
+    fn size(&self) -> usize {
+        self.size
+        }
+
+    fn write(&mut self, text: &str, cursor: Option<usize>) {
+        #[cfg(unix)]
+        let mut wide_text: Vec<WideChar>=text.chars().map(|character| character as WideChar).collect();
+        #[cfg(windows)]
+        let mut wide_text: Vec<WideChar>=text.encode_utf16().collect();
+        wide_text.push(0);
+
+        //Cursor positions start from 1, 0 turns the cursor off
+        let cursor=cursor.map_or(0, |cursor| cursor as c_int+1);
+
+        unsafe {
+            if let Ok(write_text)=self.library.get::<unsafe extern "C" fn(c_int, *const WideChar) -> c_int>(b"brlapi_writeWText\0") {
+                write_text(cursor, wide_text.as_ptr());
+                }
+            }
+        }
+
+    fn read_command(&mut self) -> Option<BrailleCommand> {
+        let mut code: u64=0;
+
+        unsafe {
+            let read_key=self.library.get::<unsafe extern "C" fn(c_int, *mut u64) -> c_int>(b"brlapi_readKey\0").ok()?;
+            if read_key(0, &mut code)!=1 {
+                return None;
+                }
+            }
+
+        BrlApiDisplay::command(code)
+        }
+
+    }
+
+impl Drop for BrlApiDisplay {
+
+    fn drop(&mut self) {
+        unsafe {
+            if let Ok(leave_tty_mode)=self.library.get::<unsafe extern "C" fn() -> c_int>(b"brlapi_leaveTtyMode\0") {
+                leave_tty_mode();
+                }
+            if let Ok(close_connection)=self.library.get::<unsafe extern "C" fn()>(b"brlapi_closeConnection\0") {
+                close_connection();
+                }
+            }
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn key_codes_are_decoded() {
+        assert!(matches!(BrlApiDisplay::command(KEY_TYPE_COMMAND | COMMAND_PAN_RIGHT), Some(BrailleCommand::PanRight)));
+        assert!(matches!(BrlApiDisplay::command(KEY_TYPE_COMMAND | COMMAND_BLOCK_ROUTE | 7), Some(BrailleCommand::Route(7))));
+        assert!(BrlApiDisplay::command(COMMAND_PAN_RIGHT).is_none());
+        }
+
+    }
@@ -0,0 +1,232 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+mod brlapi;
+mod virtual_display;
+
+pub use brlapi::BrlApiDisplay;
+pub use virtual_display::VirtualDisplay;
+
+pub enum BrailleCommand {
+    PanLeft,
+    PanRight,
+    Route(usize),
+    LineUp,
+    LineDown,
+    }
+
+//What the editor needs to do in response to a command of the display
+
+#[derive(Debug, PartialEq)]
+pub enum BrailleAction {
+    MoveCaret(usize),
+    PreviousLine,
+    NextLine,
+    }
+
+pub trait BrailleDisplay {
+    fn size(&self) -> usize;
+    fn write(&mut self, text: &str, cursor: Option<usize>);
+    fn read_command(&mut self) -> Option<BrailleCommand>;
+    }
+
+//Presents the current line preceded by its depth, in windows of the size of the display
+
+pub struct Braille {
+    display: Box<dyn BrailleDisplay>,
+    line_number: Option<usize>,
+    cells: Vec<char>,
+    indicator_length: usize,
+    caret: usize,
+    window_start: usize,
+    pan_to_ending: bool,
+    }
+
+impl Braille {
+
+    pub fn new(display: Box<dyn BrailleDisplay>) -> Braille {
+        Braille {display, line_number: None, cells: Vec::new(), indicator_length: 0, caret: 0, window_start: 0, pan_to_ending: false}
+        }
+
+    //The window follows the caret when it moves, so panning is kept until then.
+    //Panning to the previous line applies only to the line shown right after it, the navigation may have failed
+
+    pub fn show_line(&mut self, line_number: usize, depth: usize, text: &str, character_offset: usize) {
+        let pan_to_ending=std::mem::replace(&mut self.pan_to_ending, false);
+        let indicator=format!("{} ", depth+1);
+        let cells: Vec<char>=indicator.chars().chain(text.chars()).collect();
+        let caret=indicator.len()+character_offset;
+
+        if self.line_number!=Some(line_number) {
+            self.line_number=Some(line_number);
+            self.window_start=0;
+            self.cells=cells;
+            self.indicator_length=indicator.len();
+            self.caret=caret;
+
+            if pan_to_ending {
+                self.window_start=self.last_window_start();
+                }
+            else {
+                self.follow_caret();
+                }
+            }
+        else if cells!=self.cells || caret!=self.caret {
+            self.cells=cells;
+            self.indicator_length=indicator.len();
+            self.caret=caret;
+            self.follow_caret();
+            }
+        else {
+            return;
+            }
+
+        self.refresh();
+        }
+
+    pub fn execute(&mut self, command: BrailleCommand) -> Option<BrailleAction> {
+        let size=self.display.size();
+
+        match command {
+            BrailleCommand::PanLeft if self.window_start==0 => {
+                self.pan_to_ending=true;
+                return Some(BrailleAction::PreviousLine);
+                },
+            BrailleCommand::PanLeft => self.window_start=self.window_start.saturating_sub(size),
+            BrailleCommand::PanRight if self.window_start>=self.last_window_start() => return Some(BrailleAction::NextLine),
+            BrailleCommand::PanRight => self.window_start+=size,
+            BrailleCommand::Route(cell) => {
+                let position=self.window_start+cell;
+
+                if position>=self.indicator_length && position<=self.cells.len() {
+                    return Some(BrailleAction::MoveCaret(position-self.indicator_length));
+                    }
+
+                return None;
+                },
+            BrailleCommand::LineUp => return Some(BrailleAction::PreviousLine),
+            BrailleCommand::LineDown => return Some(BrailleAction::NextLine),
+            };
+
+        self.refresh();
+        None
+        }
+
+    pub fn poll(&mut self) -> Option<BrailleAction> {
+        let command=self.display.read_command()?;
+
+        self.execute(command)
+        }
+
+    //The displayed text and the position of the cursor in it
+
+    pub fn window(&self) -> (String, Option<usize>) {
+        let size=self.display.size();
+        let text=self.cells.iter().skip(self.window_start).take(size).collect();
+        let cursor=if self.caret>=self.window_start && self.caret<self.window_start+size {
+            Some(self.caret-self.window_start)
+            }
+        else {
+            None
+            };
+
+        (text, cursor)
+        }
+
+    fn follow_caret(&mut self) {
+        let size=self.display.size().max(1);
+
+        if self.caret<self.window_start || self.caret>=self.window_start+size {
+            self.window_start=self.caret/size*size;
+            }
+        }
+
+    //The caret may be placed behind the last character, so the cell behind the text belongs to the line
+
+    fn last_window_start(&self) -> usize {
+        let size=self.display.size().max(1);
+
+        self.cells.len()/size*size
+        }
+
+    fn refresh(&mut self) {
+        let (text, cursor)=self.window();
+        self.display.write(&text, cursor);
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn braille() -> Braille {
+        Braille::new(Box::new(VirtualDisplay::new(10)))
+        }
+
+    #[test]
+    fn line_is_shown_with_depth_and_caret() {
+        let mut braille=braille();
+
+        braille.show_line(0, 1, "def a():", 4);
+        assert_eq!(braille.window(), ("2 def a():".to_string(), Some(6)));
+        }
+
+    #[test]
+    fn window_follows_the_caret_and_pans() {
+        let mut braille=braille();
+
+        braille.show_line(0, 0, "print('Hello world')", 12);
+        assert_eq!(braille.window(), ("ello world".to_string(), Some(4)));
+
+        assert_eq!(braille.execute(BrailleCommand::PanLeft), None);
+        assert_eq!(braille.window(), ("1 print('H".to_string(), None));
+        braille.show_line(0, 0, "print('Hello world')", 12);
+        assert_eq!(braille.window().1, None);
+
+        assert_eq!(braille.execute(BrailleCommand::PanLeft), Some(BrailleAction::PreviousLine));
+        braille.show_line(1, 0, "x=1234567890", 0);
+        assert_eq!(braille.window(), ("7890".to_string(), None));
+
+        assert_eq!(braille.execute(BrailleCommand::PanRight), Some(BrailleAction::NextLine));
+        }
+
+    #[test]
+    fn failed_pan_to_the_previous_line_is_forgotten() {
+        let mut braille=braille();
+
+        braille.show_line(0, 0, "x=1234567890", 0);
+        assert_eq!(braille.execute(BrailleCommand::PanLeft), Some(BrailleAction::PreviousLine));
+        braille.show_line(0, 0, "x=1234567890", 0);
+        assert_eq!(braille.window(), ("1 x=123456".to_string(), Some(2)));
+
+        braille.show_line(1, 0, "y=1234567890", 0);
+        assert_eq!(braille.window(), ("1 y=123456".to_string(), Some(2)));
+        }
+
+    #[test]
+    fn routing_keys_move_the_caret() {
+        let mut braille=braille();
+
+        braille.show_line(0, 0, "abc", 0);
+        assert_eq!(braille.execute(BrailleCommand::Route(3)), Some(BrailleAction::MoveCaret(1)));
+        assert_eq!(braille.execute(BrailleCommand::Route(5)), Some(BrailleAction::MoveCaret(3)));
+        assert_eq!(braille.execute(BrailleCommand::Route(0)), None);
+        assert_eq!(braille.execute(BrailleCommand::Route(6)), None);
+        }
+
+    }
@@ -0,0 +1,46 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use super::{BrailleCommand, BrailleDisplay};
+
+//A display without hardware, the presented window is read from Braille, as the terminal frontend does
+
+pub struct VirtualDisplay {
+    size: usize,
+    }
+
+impl VirtualDisplay {
+
+    pub fn new(size: usize) -> VirtualDisplay {
+        VirtualDisplay {size}
+        }
+
+    }
+
+impl BrailleDisplay for VirtualDisplay {
+
+    fn size(&self) -> usize {
+        self.size
+        }
+
+    fn write(&mut self, _text: &str, _cursor: Option<usize>) {
+        }
+
+    fn read_command(&mut self) -> Option<BrailleCommand> {
+        None
+        }
+
+    }
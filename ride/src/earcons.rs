@@ -0,0 +1,159 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::time::Duration;
+
+use bass::{Audio, BassError, Sound, Waveform};
+use serde::{Serialize, Deserialize};
+
+const MAX_DEPTH: usize=16;
+const DEPTH_TONE_DURATION: Duration=Duration::from_millis(80);
+const BLOCK_TONE_DURATION: Duration=Duration::from_millis(40);
+const TICK_DURATION: Duration=Duration::from_millis(8);
+const TICK_FREQUENCY: f32=2000.0;
+const TICK_VOLUME: f32=0.3;
+
+//Depth tones replace the chil sound, block tones mark lines introducing a block
+//With positional audio, earcons are panned by the caret column and moving by characters ticks
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EarconSettings {
+    pub depth_tones: bool,
+    pub block_tones: bool,
+    pub base_frequency: f32,
+    pub semitones_per_level: f32,
+    pub positional_audio: bool,
+    }
+
+impl Default for EarconSettings {
+
+    fn default() -> Self {
+        EarconSettings {depth_tones: true, block_tones: false, base_frequency: 220.0, semitones_per_level: 2.0, positional_audio: false}
+        }
+
+    }
+
+//The tones are generated upfront, levels deeper than the maximum share the highest one
+//If any of them can't be generated, Ride falls back to the chil sound
+
+pub struct Earcons {
+    depth_tones: Vec<Sound>,
+    block_tones: Vec<Sound>,
+    tick: Option<Sound>,
+    positional_audio: bool,
+    }
+
+impl Earcons {
+
+    pub fn new(audio: &Audio, settings: &EarconSettings) -> Earcons {
+        let tones=|enabled: bool, waveform: Waveform, duration: Duration| -> Vec<Sound> {
+            if !enabled {
+                return Vec::new();
+                }
+
+            (0..=MAX_DEPTH).map(|depth| audio.generate(waveform, Earcons::frequency(settings, depth), duration))
+            .collect::<Result<Vec<Sound>, BassError>>()
+            .unwrap_or_default()
+            };
+
+        let tick=if settings.positional_audio {
+            audio.generate(Waveform::Square, TICK_FREQUENCY, TICK_DURATION).ok()
+            }
+        else {
+            None
+            };
+
+        Earcons {
+            depth_tones: tones(settings.depth_tones, Waveform::Sine, DEPTH_TONE_DURATION),
+            block_tones: tones(settings.block_tones, Waveform::Square, BLOCK_TONE_DURATION),
+            tick,
+            positional_audio: settings.positional_audio,
+            }
+        }
+
+    pub fn has_depth_tones(&self) -> bool {
+        !self.depth_tones.is_empty()
+        }
+
+    //Without positional audio, everything plays in the centre
+
+    pub fn pan(&self, column: usize, line_length: usize) -> f32 {
+        if !self.positional_audio || line_length==0 {
+            return 0.0;
+            }
+
+        (column.min(line_length) as f32/line_length as f32)*2.0-1.0
+        }
+
+    fn play(&self, sound: &Sound, pan: f32) {
+        sound.play_with(pan, 1.0).ok();
+        }
+
+    pub fn play_depth(&self, depth: usize, pan: f32) {
+        if let Some(tone)=self.depth_tones.get(depth.min(MAX_DEPTH)) {
+            self.play(tone, pan);
+            }
+        }
+
+    //The block tone has the pitch of the level it introduces
+
+    pub fn play_block(&self, depth: usize, pan: f32) {
+        if let Some(tone)=self.block_tones.get((depth+1).min(MAX_DEPTH)) {
+            self.play(tone, pan);
+            }
+        }
+
+    pub fn play_tick(&self, pan: f32) {
+        if let Some(tick)=&self.tick {
+            tick.play_with(pan, TICK_VOLUME).ok();
+            }
+        }
+
+    fn frequency(settings: &EarconSettings, depth: usize) -> f32 {
+        settings.base_frequency*2f32.powf(settings.semitones_per_level*depth as f32/12.0)
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn deeper_levels_sound_higher() {
+        let settings=EarconSettings {semitones_per_level: 12.0, ..EarconSettings::default()};
+
+        assert_eq!(Earcons::frequency(&settings, 0), 220.0);
+        assert_eq!(Earcons::frequency(&settings, 2), 880.0);
+        assert_eq!(Earcons::frequency(&EarconSettings::default(), 6), 440.0);
+        }
+
+    #[test]
+    fn caret_column_is_panned() {
+        let earcons=Earcons {depth_tones: Vec::new(), block_tones: Vec::new(), tick: None, positional_audio: true};
+
+        assert_eq!(earcons.pan(0, 10), -1.0);
+        assert_eq!(earcons.pan(5, 10), 0.0);
+        assert_eq!(earcons.pan(10, 10), 1.0);
+        assert_eq!(earcons.pan(0, 0), 0.0);
+
+        let earcons=Earcons {positional_audio: false, ..earcons};
+        assert_eq!(earcons.pan(0, 10), 0.0);
+        }
+
+    }
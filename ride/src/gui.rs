@@ -0,0 +1,388 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::{mpsc, mpsc::{Receiver, RecvTimeoutError, Sender}};
+use std::thread;
+use std::time::Duration;
+
+use gtk::prelude::*;
+
+use gtk::{Application, ApplicationWindow};
+use glib::{ControlFlow, Propagation};
+
+use gio::ApplicationFlags;
+
+use crate::interface::{Appearance, Frontend, RideScreen};
+use crate::screen::KeyboardShortcut;
+use crate::view::LevelView;
+
+const BRAILLE_POLLING_INTERVAL: Duration=Duration::from_millis(100);
+
+pub enum GtkThreadMessage {
+    KeyPress(KeyboardShortcut),
+    //WindowCreation(Arc<ApplicationWindow>),
+    ApplicationExit,
+    }
+
+pub enum RideThreadMessage {
+    SetWindowTitle(String),
+    ShowView(LevelView),
+    SetAppearance(Appearance),
+    SetScreenReaderMode(bool),
+    }
+
+pub fn run(file_path: String) {
+    let (gtk_sender, ride_receiver) = mpsc::channel::<GtkThreadMessage>();
+    let (ride_sender, gtk_receiver) = mpsc::channel::<RideThreadMessage>();
+    let gtk_receiver=Rc::new(gtk_receiver);
+
+    let handle=launch_ride_thread(file_path, ride_sender, ride_receiver);
+
+    let application=Application::new(None, ApplicationFlags::HANDLES_OPEN);
+
+    connect_application_activate_handler(&application, gtk_sender.clone(), gtk_receiver.clone());
+    connect_application_open_handler(&application, gtk_sender.clone(), gtk_receiver.clone());
+
+    application.run();
+
+    gtk_sender.send(GtkThreadMessage::ApplicationExit).unwrap();
+    handle.join().unwrap();
+    }
+
+fn activate_window(app: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
+
+    let window=Rc::new(ApplicationWindow::new(app));
+    window.set_title("Ride");
+    window.set_default_size(800, 600);
+
+    let level_display=Rc::new(LevelDisplay::new());
+    window.add(&level_display.container);
+
+    connect_key_press_handler(window.clone(), level_display.clone(), gtk_sender);
+    setup_timer(window.clone(), level_display, gtk_receiver);
+
+    window.show_all();
+    }
+
+fn launch_ride_thread(file_path: String, ride_sender: Sender<RideThreadMessage>, ride_receiver: Receiver<GtkThreadMessage>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut ride_screen=RideScreen::new(&file_path, Box::new(GtkFrontend {ride_sender}));
+
+        loop {
+            match ride_receiver.recv_timeout(BRAILLE_POLLING_INTERVAL) {
+                Ok(GtkThreadMessage::KeyPress(key)) => ride_screen.on_key_pressed(&key),
+                Ok(GtkThreadMessage::ApplicationExit) | Err(RecvTimeoutError::Disconnected) => break,
+                Err(RecvTimeoutError::Timeout) => ride_screen.poll_braille(),
+                };
+            }
+
+        ride_screen.on_exit();
+        })
+    }
+fn connect_application_activate_handler(application: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
+    application.connect_activate(move |app| {
+        activate_window(app, gtk_sender.clone(), gtk_receiver.clone());
+        });
+    }
+fn connect_application_open_handler(application: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
+    application.connect_open(move |app, _, _| {
+        activate_window(app, gtk_sender.clone(), gtk_receiver.clone());
+        });
+    }
+
+fn connect_key_press_handler(window: Rc<ApplicationWindow>, level_display: Rc<LevelDisplay>, gtk_sender: Sender<GtkThreadMessage>) {
+    window.connect_key_press_event(move |_, key| {
+        let keyboard_shortcut=KeyboardShortcut::from_eventkey(key);
+        gtk_sender.send(GtkThreadMessage::KeyPress(keyboard_shortcut)).unwrap();
+
+        //The focused line view must not move its own caret
+
+        if level_display.screen_reader_mode.get() {
+            return Propagation::Stop;
+            }
+
+        Propagation::Proceed
+        });
+    }
+fn setup_timer(window: Rc<ApplicationWindow>, level_display: Rc<LevelDisplay>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
+    glib::source::timeout_add_local(Duration::from_millis(100), move || {
+        while let Ok(message) = gtk_receiver.try_recv() {
+            match message {
+                RideThreadMessage::SetWindowTitle(title) => window.set_title(&title),
+                RideThreadMessage::ShowView(view) => level_display.show(&view),
+                RideThreadMessage::SetAppearance(appearance) => level_display.set_appearance(&appearance),
+                RideThreadMessage::SetScreenReaderMode(enabled) => level_display.set_screen_reader_mode(enabled),
+                };
+            }
+
+        ControlFlow::Continue
+        });
+    }
+
+//Shows the lines of the current level below the line introducing them, with a status bar
+//In the screen reader mode, the current line is exposed through a focused text view, so screen readers follow the caret
+
+struct LevelDisplay {
+    container: gtk::Box,
+    text_view: gtk::TextView,
+    line_view: gtk::TextView,
+    status_bar: gtk::Label,
+    css_provider: gtk::CssProvider,
+    screen_reader_mode: Cell<bool>,
+    }
+
+impl LevelDisplay {
+
+    fn new() -> LevelDisplay {
+        let container=gtk::Box::new(gtk::Orientation::Vertical, 0);
+        let scrolled_window=gtk::ScrolledWindow::new(gtk::Adjustment::NONE, gtk::Adjustment::NONE);
+        let text_view=gtk::TextView::new();
+        let line_view=gtk::TextView::new();
+        let status_bar=gtk::Label::new(None);
+
+        //Keys go to the window, the view only displays the text
+
+        text_view.set_editable(false);
+        text_view.set_cursor_visible(false);
+        text_view.set_can_focus(false);
+        text_view.set_monospace(true);
+        text_view.set_left_margin(8);
+
+        line_view.set_editable(false);
+        line_view.set_cursor_visible(true);
+        line_view.set_no_show_all(true);
+        if let Some(accessible)=line_view.accessible() {
+            accessible.set_name("Current line");
+            }
+
+        status_bar.set_xalign(0.0);
+        status_bar.set_margin_start(8);
+
+        scrolled_window.add(&text_view);
+        container.pack_start(&scrolled_window, true, true, 0);
+        container.pack_start(&line_view, false, false, 4);
+        container.pack_start(&status_bar, false, false, 4);
+
+        let buffer=text_view.buffer().unwrap();
+        for name in ["parent", "current", "selected", "caret"] {
+            buffer.tag_table().unwrap().add(&gtk::TextTag::new(Some(name)));
+            }
+
+        let css_provider=gtk::CssProvider::new();
+        gtk::StyleContext::add_provider_for_screen(&gdk::Screen::default().unwrap(), &css_provider, gtk::STYLE_PROVIDER_PRIORITY_APPLICATION);
+
+        let level_display=LevelDisplay {container, text_view, line_view, status_bar, css_provider, screen_reader_mode: Cell::new(false)};
+        level_display.set_appearance(&Appearance::default());
+
+        level_display
+        }
+
+    fn show(&self, view: &LevelView) {
+        let buffer=self.text_view.buffer().unwrap();
+        let prefix=if view.parent_line.is_some() { "    " } else { "" };
+
+        let mut lines: Vec<(String, bool, bool, bool)>=Vec::new();
+        let mut caret=(0, 0);
+
+        if let Some(line)=&view.parent_line {
+            lines.push((line.text.clone(), true, line.current, false));
+            }
+        for line in &view.lines {
+            if line.current {
+                caret=(lines.len(), prefix.chars().count());
+                }
+
+            let text=format!("{}{}{}", prefix, line.text, if line.has_block { " ..." } else { "" });
+            lines.push((text, false, line.current, line.selected));
+            }
+
+        let text: Vec<&str>=lines.iter().map(|line| line.0.as_str()).collect();
+        buffer.set_text(&text.join("\n"));
+
+        for (index, (_, parent, current, selected)) in lines.iter().enumerate() {
+            let start=buffer.iter_at_line(index as i32);
+            let mut end=start;
+            end.forward_line();
+
+            for (name, applies) in [("parent", parent), ("current", current), ("selected", selected)] {
+                if *applies {
+                    buffer.apply_tag_by_name(name, &start, &end);
+                    }
+                }
+            }
+
+        let caret_start=buffer.iter_at_line_offset(caret.0 as i32, (caret.1+view.character_offset) as i32);
+        let mut caret_end=caret_start;
+        caret_end.forward_char();
+        buffer.apply_tag_by_name("caret", &caret_start, &caret_end);
+
+        buffer.place_cursor(&caret_start);
+        if let Some(insert)=buffer.get_insert() {
+            self.text_view.scroll_to_mark(&insert, 0.1, false, 0.0, 0.0);
+            }
+
+        self.status_bar.set_text(&view.status());
+
+        if self.screen_reader_mode.get() {
+            self.show_current_line(view);
+            }
+        }
+
+    //Replacing the text emits text-changed events only for a different line, placing the cursor emits caret-moved
+
+    fn show_current_line(&self, view: &LevelView) {
+        let buffer=self.line_view.buffer().unwrap();
+        let text=view.current_line().map_or("", |line| line.text.as_str());
+
+        if buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).as_deref()!=Some(text) {
+            buffer.set_text(text);
+            }
+
+        buffer.place_cursor(&buffer.iter_at_offset(view.character_offset as i32));
+        }
+
+    fn set_screen_reader_mode(&self, enabled: bool) {
+        self.screen_reader_mode.set(enabled);
+        self.line_view.set_visible(enabled);
+
+        if enabled {
+            self.line_view.grab_focus();
+            }
+        }
+
+    fn set_appearance(&self, appearance: &Appearance) {
+        let mut css=format!("textview, label {{ font-size: {}pt; }}\n", appearance.font_size);
+
+        let (current, selected, caret_background, caret_foreground)=if appearance.high_contrast {
+            css+="window, textview text, label { background-color: #000000; color: #ffffff; }\n";
+
+            ("#404000", "#0050a0", "#ffff00", "#000000")
+            }
+        else {
+            ("#e8eefc", "#b5cff5", "#000000", "#ffffff")
+            };
+
+        self.css_provider.load_from_data(css.as_bytes()).unwrap();
+
+        let tag_table=self.text_view.buffer().unwrap().tag_table().unwrap();
+        let tag=|name: &str| tag_table.lookup(name).unwrap();
+
+        tag("parent").set_property("weight", 700);
+        tag("current").set_property("paragraph-background", current);
+        tag("selected").set_property("paragraph-background", selected);
+        tag("caret").set_property("background", caret_background);
+        tag("caret").set_property("foreground", caret_foreground);
+        }
+
+    }
+
+struct GtkFrontend {
+    ride_sender: Sender<RideThreadMessage>,
+    }
+
+impl Frontend for GtkFrontend {
+
+    fn set_title(&self, title: &str) {
+        self.ride_sender.send(RideThreadMessage::SetWindowTitle(title.to_string())).unwrap();
+        }
+
+    fn show(&self, view: LevelView) {
+        self.ride_sender.send(RideThreadMessage::ShowView(view)).unwrap();
+        }
+
+    fn set_appearance(&self, appearance: &Appearance) {
+        self.ride_sender.send(RideThreadMessage::SetAppearance(appearance.clone())).unwrap();
+        }
+
+    fn set_screen_reader_mode(&self, enabled: bool) {
+        self.ride_sender.send(RideThreadMessage::SetScreenReaderMode(enabled)).unwrap();
+        }
+
+    fn message_box(&self, title: &str, message: &str) {
+        let (title, message)=(title.to_string(), message.to_string());
+        let (message_box_sender, message_box_receiver)=std::sync::mpsc::channel::<()>();
+
+        glib::source::idle_add_once(move || {
+            let dialog = gtk::Dialog::new();
+            dialog.set_title(&title);
+
+            let label = gtk::Label::new(Some(&message));
+
+            dialog.content_area().add(&label);
+
+            dialog.add_button("Ok", gtk::ResponseType::Ok);
+
+            dialog.show_all();
+
+            dialog.run();
+
+            dialog.close();
+
+            message_box_sender.send(()).unwrap();
+            });
+
+        message_box_receiver.recv().unwrap();
+        }
+
+    fn input_box(&self, title: &str, message: &str) -> Option<String> {
+        let (title, message)=(title.to_string(), message.to_string());
+        let (input_box_sender, input_box_receiver)=std::sync::mpsc::channel::<Option<String>>();
+
+        glib::source::idle_add_once(move || {
+            let dialog = std::rc::Rc::new(gtk::Dialog::new());
+            dialog.set_title(&title);
+
+            let label = gtk::Label::new(Some(&message));
+            let entry = gtk::Entry::new();
+
+            dialog.content_area().add(&label);
+            dialog.content_area().add(&entry);
+
+            dialog.add_button("Ok", gtk::ResponseType::Ok);
+            dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+
+            let dialog_clone=dialog.clone();
+            entry.connect_key_press_event(move |_, key| {
+                if key.keyval()==gdk::keys::constants::Return {
+                    dialog_clone.response(gtk::ResponseType::Ok);
+                    return Propagation::Stop;
+                    }
+
+                Propagation::Proceed
+                });
+
+            dialog.show_all();
+
+            let response=dialog.run();
+
+            let result=if response==gtk::ResponseType::Ok {
+                Some(entry.text().to_string())
+                }
+            else {
+                None
+                };
+
+            dialog.close();
+
+            input_box_sender.send(result).unwrap();
+            });
+
+        input_box_receiver.recv().unwrap()
+        }
+
+    }
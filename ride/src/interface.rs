@@ -14,62 +14,139 @@
 * along with this program. If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::env;
 use std::path::Path;
 
-use bass::Sound;
+use std::fs;
+use std::error::Error;
+
+use serde::{Serialize, Deserialize};
+
+use ride_core::TextRenderer;
+use crate::earcons::EarconSettings;
+use crate::sounds::SoundSettings;
+use crate::speech::{ProsodySettings, SpeechBackendKind, SpeechPriorities, VoiceSettings};
+
+#[derive(Serialize, Deserialize)]
+enum Value {
+    Bool(bool),
+    TextRenderer(TextRenderer),
+    }
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub appearance: Appearance,
+    pub beep_on_capital_characters: bool,
+    pub braille: BrailleSettings,
+    pub earcons: EarconSettings,
+    pub screen_reader_mode: bool,
+    pub sounds: SoundSettings,
+    pub speech: SpeechSettings,
+    pub text_renderer: TextRenderer,
+    pub typing_echo: TypingEcho,
+    }
+
+//How the text is displayed by frontends with control over fonts and colors
 
-pub struct Resources {
-    pub bump: Sound,
-    pub chil: Sound,
-    pub capital: Sound,
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub font_size: u32,
+    pub high_contrast: bool,
     }
 
-impl Resources {
+impl Default for Appearance {
 
-    pub fn new() -> Resources {
+    fn default() -> Self {
+        Appearance {font_size: 16, high_contrast: false}
+        }
 
-        let mut bump=Sound::new();
-        let mut chil=Sound::new();
-        let mut capital=Sound::new();
+    }
 
-        let mut root=env::current_exe().unwrap();
-        root.pop();
+//What is spoken while typing, words are spoken once a character which can't be a part of an identifier follows them
 
-        let sounds_root=Path::join(&root, "Sounds");
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum TypingEcho {
+    Off,
+    Characters,
+    Words,
+    Both,
+    }
 
-        bump.load(Path::join(&sounds_root, "Bump.wav").to_str().unwrap());
-        chil.load(Path::join(&sounds_root, "Chil.wav").to_str().unwrap());
-        capital.load(Path::join(&sounds_root, "Capital.wav").to_str().unwrap());
+impl TypingEcho {
 
-        Resources {bump, chil, capital}
+    pub fn echoes_characters(self) -> bool {
+        self==TypingEcho::Characters || self==TypingEcho::Both
         }
+
+    pub fn echoes_words(self) -> bool {
+        self==TypingEcho::Words || self==TypingEcho::Both
+        }
+
     }
 
-use std::fs;
-use std::error::Error;
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum BrailleDisplayKind {
+    None,
+    BrlApi,
+    Virtual,
+    }
 
-use serde::{Serialize, Deserialize};
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrailleSettings {
+    pub display: BrailleDisplayKind,
+    pub virtual_display_size: usize,
+    }
 
-use crate::core::TextRenderer;
+impl Default for BrailleSettings {
+
+    fn default() -> Self {
+        BrailleSettings {display: BrailleDisplayKind::None, virtual_display_size: 40}
+        }
 
-#[derive(Serialize, Deserialize)]
-enum Value {
-    Bool(bool),
-    TextRenderer(TextRenderer),
     }
 
+//The backend is used if it can be opened, otherwise the fallbacks are tried in order
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(default)]
-pub struct Settings {
-    pub beep_on_capital_characters: bool,
-    pub text_renderer: TextRenderer,
+pub struct SpeechSettings {
+    pub backend: SpeechBackendKind,
+    pub fallback: Vec<SpeechBackendKind>,
+    pub log_file: String,
+    pub voice: VoiceSettings,
+    pub priorities: SpeechPriorities,
+    pub prosody: ProsodySettings,
+    }
+
+impl SpeechSettings {
+
+    pub fn backends(&self) -> Vec<SpeechBackendKind> {
+        std::iter::once(self.backend).chain(self.fallback.iter().copied()).collect()
+        }
+
+    }
+
+impl Default for SpeechSettings {
+
+    fn default() -> Self {
+        SpeechSettings {backend: SpeechBackendKind::Native, fallback: vec![SpeechBackendKind::Espeak], log_file: String::new(), voice: VoiceSettings::default(), priorities: SpeechPriorities::default(), prosody: ProsodySettings::default()}
+        }
+
     }
 
 impl Settings {
 
     pub fn new() -> Settings {
-        Settings {beep_on_capital_characters: true, text_renderer: TextRenderer::new()}
+        Settings {appearance: Appearance::default(), beep_on_capital_characters: true, braille: BrailleSettings::default(), earcons: EarconSettings::default(), screen_reader_mode: false, sounds: SoundSettings::default(), speech: SpeechSettings::default(), text_renderer: TextRenderer::new(), typing_echo: TypingEcho::Off}
+        }
+
+    pub fn load() -> Settings {
+        Settings::from_file(&Settings::get_settings_file_path("ride", "settings.yaml"))
+        .unwrap_or(Settings::new())
         }
 
     pub fn from_file(file_path: &str) -> Result<Settings, Box<dyn Error>> {
@@ -98,36 +175,88 @@ impl Settings {
 
 use std::sync::mpsc::Sender;
 
+use bass::Audio;
 use copypasta::{ClipboardContext, ClipboardProvider};
-use gtk::prelude::*;
-use glib::Propagation;
 
-use crate::core::{RideText, SearchDirection};
+use ride_core::{RideText, SearchDirection};
+use ride_core::reformat::{ReformatDirection, ReformatPreset, ReformatScope};
+use crate::braille::{Braille, BrailleAction, BrlApiDisplay, VirtualDisplay};
+use crate::earcons::Earcons;
+use crate::sounds::{SoundEvent, Sounds};
 use crate::screen::{KeyboardShortcutsManager, KeyboardShortcut, Key};
 use crate::speech::Speech;
+use crate::view::LevelView;
+
+const VIEW_CAPACITY: usize=200;
+const VOICE_PARAMETER_STEP: i32=10;
+
+//The parts of the user interface provided by GTK or the terminal
+
+pub trait Frontend {
+    fn set_title(&self, title: &str);
+    fn message_box(&self, title: &str, message: &str);
+    fn input_box(&self, title: &str, message: &str) -> Option<String>;
+    fn show(&self, view: LevelView);
+    fn set_appearance(&self, appearance: &Appearance);
+    fn set_screen_reader_mode(&self, enabled: bool);
+    }
 
 pub struct RideScreen<'a> {
-    clipboard_context: ClipboardContext,
+    braille: Option<Braille>,
+    clipboard_context: Option<ClipboardContext>,
+    clipboard_text: String,
     content: RideText,
+    earcons: Earcons,
     lastly_searched_phrase: String,
     keyboard_shortcuts_manager: KeyboardShortcutsManager<'a, RideScreen<'a>>,
-    resources: Resources,
     settings: Settings,
+    sounds: Sounds,
     speech: Speech,
-    ride_sender: Sender<RideThreadMessage>,
+    frontend: Box<dyn Frontend>,
+    //Declared last, so the sounds are dropped before the audio they were loaded by
+    audio: Audio,
     }
 
 impl<'a> RideScreen<'a> {
 
-    pub fn new(file_path: &str, ride_sender: Sender<RideThreadMessage>) -> Self {
-        let clipboard_context=ClipboardContext::new().unwrap();
+    pub fn new(file_path: &str, frontend: Box<dyn Frontend>) -> Self {
+        //Headless machines have no clipboard, text is kept in the editor then
+        let clipboard_context=ClipboardContext::new().ok();
+        let clipboard_text=String::new();
 
-        let resources=Resources::new();
-        let speech=Speech::new("ride");
         let content=RideText::new();
         let lastly_searched_phrase="".to_string();
-        let settings=Settings::from_file(&Settings::get_settings_file_path("ride", "settings.yaml"))
-        .unwrap_or(Settings::new());
+        let settings=Settings::load();
+        let mut speech=Speech::new("ride", &settings.speech.backends(), &settings.speech.log_file);
+        speech.set_voice(&settings.speech.voice);
+        speech.set_priorities(&settings.speech.priorities);
+        speech.set_prosody(&settings.speech.prosody);
+
+        //Ride works also without sounds, the silent output is used where BASS can't be initialized
+        let audio=match Audio::init(settings.sounds.max_voices) {
+            Ok(audio) => audio,
+            Err(error) => {
+                speech.speak_notification(&format!("Unable to initialize audio, {}", error));
+                Audio::silent()
+                },
+            };
+        audio.set_volume(settings.sounds.volume).ok();
+
+        let earcons=Earcons::new(&audio, &settings.earcons);
+        let sounds=Sounds::new(&audio, &settings.sounds);
+
+        if !sounds.errors.is_empty() {
+            speech.speak_notification(&format!("Unable to load sounds, {}", sounds.errors.join(", ")));
+            }
+
+        //In the screen reader mode, the frontend exposes the caret to the user's screen reader instead
+        speech.set_muted(settings.screen_reader_mode);
+
+        let braille=match settings.braille.display {
+            BrailleDisplayKind::None => None,
+            BrailleDisplayKind::BrlApi => BrlApiDisplay::open().ok().map(|display| Braille::new(Box::new(display))),
+            BrailleDisplayKind::Virtual => Some(Braille::new(Box::new(VirtualDisplay::new(settings.braille.virtual_display_size)))),
+            };
 
         let mut keyboard_shortcuts_manager=KeyboardShortcutsManager::new();
 
@@ -166,22 +295,45 @@ impl<'a> RideScreen<'a> {
         keyboard_shortcuts_manager.add_shortcut(true, false, false, Key::X, &Self::cut);
         keyboard_shortcuts_manager.add_shortcut(true, false, false, Key::V, &Self::paste);
         keyboard_shortcuts_manager.add_shortcut(true, false, false, Key::I, &Self::reformat);
+        keyboard_shortcuts_manager.add_shortcut(true, true, false, Key::I, &Self::reformat_selection);
+        keyboard_shortcuts_manager.add_shortcut(true, false, true, Key::I, &Self::generate_marks);
+        keyboard_shortcuts_manager.add_shortcut(true, true, true, Key::I, &Self::generate_marks_for_selection);
+        keyboard_shortcuts_manager.add_shortcut(true, true, false, Key::J, &Self::toggle_compact_output);
 
         //Settings shortcuts
 
         keyboard_shortcuts_manager.add_shortcut(true, false, false, Key::R, &Self::add_character_definition);
         keyboard_shortcuts_manager.add_shortcut(true, true, false, Key::R, &Self::add_string_definition);
 
-        let mut result=Self {clipboard_context, content, lastly_searched_phrase, keyboard_shortcuts_manager, resources, settings, speech, ride_sender};
-
+        //Speech shortcuts
+
+        keyboard_shortcuts_manager.add_shortcut(false, false, false, Key::Control, &Self::stop_speech);
+        //Desktops reserve many Ctrl+Alt chords for workspaces and locking, Alt+Shift ones are left to applications
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::Up, &Self::increase_speech_rate);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::Down, &Self::decrease_speech_rate);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::PageUp, &Self::increase_speech_pitch);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::PageDown, &Self::decrease_speech_pitch);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::Home, &Self::increase_speech_volume);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::End, &Self::decrease_speech_volume);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::Left, &Self::select_previous_voice);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::Right, &Self::select_next_voice);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::L, &Self::set_speech_language);
+        keyboard_shortcuts_manager.add_shortcut(false, true, true, Key::M, &Self::set_speech_module);
+        keyboard_shortcuts_manager.add_shortcut(true, false, true, Key::S, &Self::set_sound_theme);
+
+        let mut result=Self {braille, clipboard_context, clipboard_text, content, earcons, lastly_searched_phrase, keyboard_shortcuts_manager, settings, sounds, speech, frontend, audio};
+
+        result.frontend.set_appearance(&result.settings.appearance);
+        result.frontend.set_screen_reader_mode(result.settings.screen_reader_mode);
         result.load_from_file(file_path);
+        result.update_view();
 
         result
         }
 
     fn load_from_file(&mut self, file_path: &str) {
         if file_path.is_empty() {
-            self.ride_sender.send(RideThreadMessage::SetWindowTitle("Untitled - Ride".to_string())).unwrap();
+            self.frontend.set_title("Untitled - Ride");
             return;
             }
         if let Err(message) = self.content.load_from_file(file_path) {
@@ -191,103 +343,121 @@ impl<'a> RideScreen<'a> {
         let file_path=self.content.file_path();
         if let Some(file_path)=file_path {
             let file_name=file_path.split('/').last().unwrap();
-            self.ride_sender.send(RideThreadMessage::SetWindowTitle(format!("{} - Ride", file_name))).unwrap();
+            self.frontend.set_title(&format!("{} - Ride", file_name));
             } else {
-            self.ride_sender.send(RideThreadMessage::SetWindowTitle("Untitled - Ride".to_string())).unwrap();
+            self.frontend.set_title("Untitled - Ride");
             }
         }
 
     fn save(&mut self) {
-        self.content.save().unwrap();
+        match self.content.save() {
+            Ok(()) => self.play_sound(SoundEvent::Saved),
+            Err(message) => {
+                self.play_sound(SoundEvent::Error);
+                self.message_box("Error", &message);
+                },
+            };
         }
 
     //Navigation functions
 
     fn navigate_to_previous_line(&mut self) {
         self.content.cancel_selection();
-        if !self.content.navigate_to_previous_line() {
-            self.resources.bump.play();
+        if self.content.navigate_to_previous_line().is_err() {
+            self.play_sound(SoundEvent::Boundary);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.play_block_tone();
+        self.speak_current_line();
         }
 
     fn navigate_to_next_line(&mut self) {
         self.content.cancel_selection();
-        if !self.content.navigate_to_next_line() {
-            self.resources.bump.play();
+        if self.content.navigate_to_next_line().is_err() {
+            self.play_sound(SoundEvent::Boundary);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.play_block_tone();
+        self.speak_current_line();
         }
 
     fn navigate_to_previous_character(&mut self) {
         self.content.cancel_selection();
-        if !self.content.navigate_to_previous_character() {
-            self.resources.bump.play();
-            }
+        match self.content.navigate_to_previous_character() {
+            Ok(()) => self.earcons.play_tick(self.caret_pan()),
+            Err(_) => self.play_sound(SoundEvent::Boundary),
+            };
         self.speak_character(self.content.get_current_character());
         }
 
     fn navigate_to_next_character(&mut self) {
         self.content.cancel_selection();
-        if !self.content.navigate_to_next_character() {
-            self.resources.bump.play();
-            }
+        match self.content.navigate_to_next_character() {
+            Ok(()) => self.earcons.play_tick(self.caret_pan()),
+            Err(_) => self.play_sound(SoundEvent::Boundary),
+            };
         self.speak_character(self.content.get_current_character());
         }
 
     fn navigate_to_area_beginning(&mut self) {
         self.content.cancel_selection();
         self.content.navigate_to_area_beginning();
-        self.resources.bump.play();
-        self.speak_text(&self.content.get_current_line());
+        self.play_sound(SoundEvent::Boundary);
+        self.play_block_tone();
+        self.speak_current_line();
         }
 
     fn navigate_to_area_ending(&mut self) {
         self.content.cancel_selection();
         self.content.navigate_to_area_ending();
-        self.resources.bump.play();
-        self.speak_text(&self.content.get_current_line());
+        self.play_sound(SoundEvent::Boundary);
+        self.play_block_tone();
+        self.speak_current_line();
         }
 
     fn navigate_to_line_beginning(&mut self) {
         self.content.navigate_to_line_beginning();
+        self.earcons.play_tick(self.caret_pan());
         }
 
     fn navigate_to_line_ending(&mut self) {
         self.content.navigate_to_line_ending();
+        self.earcons.play_tick(self.caret_pan());
         }
 
     fn increase_indentation_level(&mut self) {
         self.content.cancel_selection();
-        if self.content.increase_indentation_level() {
-            self.resources.chil.play();
+        if self.content.increase_indentation_level().is_ok() {
+            self.play_level_change(SoundEvent::LevelEntered);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.speak_current_line();
         }
 
     fn decrease_indentation_level(&mut self) {
         self.content.cancel_selection();
-        if self.content.decrease_indentation_level() {
-            self.resources.chil.play();
+        if self.content.decrease_indentation_level().is_ok() {
+            self.play_level_change(SoundEvent::LevelLeft);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.speak_current_line();
         }
 
     fn jump_to_line(&mut self) {
         if let Some(text)=self.input_box("Jump to line", "Enther the number of the line to jump to.") {
             if let Ok(n)=text.trim().parse::<usize>() {
+                let original_indentation_level=self.content.current_indentation_level();
+
                 match self.content.jump_to_line(n) {
                     Ok(chil) => {
                         if chil {
-                            self.resources.chil.play();
+                            self.play_level_change_from(original_indentation_level);
                             }
                         },
                     Err(message) => {
+                        self.play_sound(SoundEvent::Error);
                         self.message_box("Error", &message);
                         },
                     };
                 }
             else {
+                self.play_sound(SoundEvent::Error);
                 self.message_box("Error", "Invalid input.");
                 }
             }
@@ -310,18 +480,7 @@ impl<'a> RideScreen<'a> {
             self.find();
             }
 
-        let original_indentation_level=self.content.current_indentation_level();
-
-        if self.content.find(&self.lastly_searched_phrase, SearchDirection::Forward) {
-            if self.content.current_indentation_level()!=original_indentation_level {
-                self.resources.chil.play();
-                }
-
-            self.speak_text(&self.content.get_current_line());
-            }
-        else {
-            self.speak_text("Not found");
-            }
+        self.find_in_direction(SearchDirection::Forward);
         }
 
     fn backward_refind(&mut self) {
@@ -329,36 +488,50 @@ impl<'a> RideScreen<'a> {
             self.find();
             }
 
-        let original_indentation_level=self.content.current_indentation_level();
+        self.find_in_direction(SearchDirection::Backward);
+        }
 
-        if self.content.find(&self.lastly_searched_phrase, SearchDirection::Backward) {
-            if self.content.current_indentation_level()!=original_indentation_level {
-                self.resources.chil.play();
-                }
+    fn find_in_direction(&mut self, direction: SearchDirection) {
+        let original_indentation_level=self.content.current_indentation_level();
 
-            self.speak_text(&self.content.get_current_line());
-            }
-        else {
-            self.speak_text("Not found");
-            }
+        match self.content.find_wrapping(&self.lastly_searched_phrase, direction) {
+            Ok(wrapped) => {
+                if wrapped {
+                    self.play_sound(SoundEvent::SearchWrapped);
+                    }
+                self.play_level_change_from(original_indentation_level);
+                self.speak_current_line();
+                },
+            Err(_) => {
+                self.play_sound(SoundEvent::NotFound);
+                self.speech.speak_notification("Not found");
+                },
+            };
         }
 
     //Editing functions
 
-    fn select_previous_line(&mut self) {
+    fn start_selection(&mut self) {
+        if self.content.selection().is_none() {
+            self.play_sound(SoundEvent::SelectionStarted);
+            }
         self.content.start_selection();
-        if !self.content.navigate_to_previous_line() {
-            self.resources.bump.play();
+        }
+
+    fn select_previous_line(&mut self) {
+        self.start_selection();
+        if self.content.navigate_to_previous_line().is_err() {
+            self.play_sound(SoundEvent::Boundary);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.speak_current_line();
         }
 
     fn select_next_line(&mut self) {
-        self.content.start_selection();
-        if !self.content.navigate_to_next_line() {
-            self.resources.bump.play();
+        self.start_selection();
+        if self.content.navigate_to_next_line().is_err() {
+            self.play_sound(SoundEvent::Boundary);
             }
-        self.speak_text(&self.content.get_current_line());
+        self.speak_current_line();
         }
 
     fn create_new_line(&mut self) {
@@ -369,17 +542,17 @@ impl<'a> RideScreen<'a> {
     fn create_new_block(&mut self) {
         self.content.cancel_selection();
         self.content.create_new_block();
-        self.resources.chil.play();
+        self.play_level_change(SoundEvent::LevelEntered);
         }
 
     fn delete_character(&mut self) {
         self.content.cancel_selection();
-        if let Some(character) = self.content.delete_character() {
+        if let Ok(character) = self.content.delete_character() {
             self.speak_character(character);
             }
         else
             {
-            self.resources.bump.play();
+            self.play_sound(SoundEvent::Boundary);
             }
         }
 
@@ -393,10 +566,11 @@ impl<'a> RideScreen<'a> {
         match self.content.get_selected_text(false) {
             Ok(text) => {
                 self.clipboard_set_text(&text);
-                self.speech.speak("Copied");
+                self.speech.speak_notification("Copied");
                 },
             Err(message) => {
-                self.speech.speak(&message);
+                self.play_sound(SoundEvent::Error);
+                self.speech.speak_notification(&message);
                 },
             };
         }
@@ -405,10 +579,11 @@ impl<'a> RideScreen<'a> {
         match self.content.get_selected_text(true) {
             Ok(text) => {
                 self.clipboard_set_text(&text);
-                self.speech.speak("Cutted");
+                self.speech.speak_notification("Cutted");
                 },
             Err(message) => {
-                self.speech.speak(&message);
+                self.play_sound(SoundEvent::Error);
+                self.speech.speak_notification(&message);
                 },
             };
         }
@@ -417,30 +592,190 @@ impl<'a> RideScreen<'a> {
         let text=self.clipboard_get_text();
         match self.content.paste(&text) {
             Ok(()) => {
-                self.speech.speak("Pasted");
+                self.play_sound(SoundEvent::Paste);
+                self.speech.speak_notification("Pasted");
                 },
             Err(message) => {
-                self.speech.speak(&message);
+                self.play_sound(SoundEvent::Error);
+                self.speech.speak_notification(&message);
                 },
             }
         }
 
     fn reformat(&mut self) {
-        if let Some(beginning_mark)=self.input_box("Reformat", "Enter the mark beginning a block") {
-            let beginning_mark=beginning_mark.trim();
+        self.run_reformat(ReformatDirection::MarksToIndentation, ReformatScope::Document);
+        }
+
+    fn reformat_selection(&mut self) {
+        self.run_reformat(ReformatDirection::MarksToIndentation, ReformatScope::SelectionOrBlock);
+        }
 
-            if beginning_mark.is_empty() {
+    fn generate_marks(&mut self) {
+        self.run_reformat(ReformatDirection::IndentationToMarks, ReformatScope::Document);
+        }
+
+    fn generate_marks_for_selection(&mut self) {
+        self.run_reformat(ReformatDirection::IndentationToMarks, ReformatScope::SelectionOrBlock);
+        }
+
+    fn run_reformat(&mut self, direction: ReformatDirection, scope: ReformatScope) {
+        let message=format!("Enter the preset ({}), or the beginning and ending mark separated by a space", ReformatPreset::NAMES.join(", "));
+
+        if let Some(input)=self.input_box("Reformat", &message) {
+            let input=input.trim();
+
+            if input.is_empty() {
                 return;
                 }
 
-            if let Some(ending_mark)=self.input_box("Reformat", "Enter the mark ending a block") {
-                let ending_mark=ending_mark.trim();
+            let preset=match ReformatPreset::from_name(input) {
+                Some(preset) => preset,
+                None => match input.split_whitespace().collect::<Vec<&str>>()[..] {
+                    [beginning_mark, ending_mark] => ReformatPreset::custom(beginning_mark, ending_mark),
+                    _ => {
+                        self.play_sound(SoundEvent::Error);
+                        self.speech.speak_notification(&format!("Unknown preset {}", input));
+                        return;
+                        },
+                    },
+                };
 
-                if ending_mark.is_empty() {
-                    return;
-                    }
+            match self.content.reformat(&preset, direction, scope) {
+                Ok(changed_lines) => match changed_lines.first() {
+                    Some(first_line) => self.speech.speak_notification(&format!("{} lines changed, first on line {}", changed_lines.len(), first_line+1)),
+                    None => self.speech.speak_notification("No changes"),
+                    },
+                Err(message) => {
+                    self.play_sound(SoundEvent::Error);
+                    self.speech.speak_notification(&message);
+                    },
+                };
+            }
+        }
+
+    fn toggle_compact_output(&mut self) {
+        match self.content.toggle_compact_output() {
+            Ok(true) => self.speech.speak_notification("Compact output"),
+            Ok(false) => self.speech.speak_notification("Pretty output"),
+            Err(message) => {
+                self.play_sound(SoundEvent::Error);
+                self.speech.speak_notification(&message);
+                },
+            };
+        }
+
+    //Speech functions
+
+    pub fn stop_speech(&mut self) {
+        self.speech.stop();
+        }
+
+    fn increase_speech_rate(&mut self) {
+        self.change_voice_parameter("Rate", |voice| &mut voice.rate, VOICE_PARAMETER_STEP);
+        }
+
+    fn decrease_speech_rate(&mut self) {
+        self.change_voice_parameter("Rate", |voice| &mut voice.rate, -VOICE_PARAMETER_STEP);
+        }
 
-                self.content.reformat(beginning_mark, ending_mark).unwrap();
+    fn increase_speech_pitch(&mut self) {
+        self.change_voice_parameter("Pitch", |voice| &mut voice.pitch, VOICE_PARAMETER_STEP);
+        }
+
+    fn decrease_speech_pitch(&mut self) {
+        self.change_voice_parameter("Pitch", |voice| &mut voice.pitch, -VOICE_PARAMETER_STEP);
+        }
+
+    fn increase_speech_volume(&mut self) {
+        self.change_voice_parameter("Volume", |voice| &mut voice.volume, VOICE_PARAMETER_STEP);
+        }
+
+    fn decrease_speech_volume(&mut self) {
+        self.change_voice_parameter("Volume", |voice| &mut voice.volume, -VOICE_PARAMETER_STEP);
+        }
+
+    fn change_voice_parameter(&mut self, name: &str, parameter: fn(&mut VoiceSettings) -> &mut i32, step: i32) {
+        let value=parameter(&mut self.settings.speech.voice);
+        *value=(*value+step).clamp(-100, 100);
+        let message=format!("{} {}", name, value);
+
+        self.speech.set_voice(&self.settings.speech.voice);
+        self.speech.speak(&message);
+        }
+
+    fn select_previous_voice(&mut self) {
+        self.select_voice(-1);
+        }
+
+    fn select_next_voice(&mut self) {
+        self.select_voice(1);
+        }
+
+    fn select_voice(&mut self, step: isize) {
+        let voices=self.speech.voices();
+        if voices.is_empty() {
+            self.speech.speak("No voices available");
+            return;
+            }
+
+        let index=match voices.iter().position(|voice| *voice==self.settings.speech.voice.voice) {
+            Some(index) => (index as isize+step).rem_euclid(voices.len() as isize) as usize,
+            None => 0,
+            };
+
+        self.settings.speech.voice.voice=voices[index].clone();
+        self.speech.set_voice(&self.settings.speech.voice);
+        self.speech.speak(&voices[index]);
+        }
+
+    fn set_speech_language(&mut self) {
+        if let Some(language)=self.input_box("Speech language", "Enter the language code, like en or sk, or leave empty for the default.") {
+            self.settings.speech.voice.language=language;
+            self.settings.speech.voice.voice.clear();
+            self.speech.set_voice(&self.settings.speech.voice);
+            self.speech.speak_notification("Language changed");
+            }
+        }
+
+    fn set_speech_module(&mut self) {
+        if let Some(module)=self.input_box("Speech module", "Enter the name of the speech synthesis module, like espeak-ng, or leave empty for the default.") {
+            self.settings.speech.voice.module=module;
+            self.settings.speech.voice.voice.clear();
+            self.speech.set_voice(&self.settings.speech.voice);
+            self.speech.speak_notification("Module changed");
+            }
+        }
+
+    //Sound functions
+
+    fn set_sound_theme(&mut self) {
+        let themes=Sounds::themes();
+        let message=if themes.is_empty() {
+            "Enter the name or path of the sound theme, or leave empty for the default sounds.".to_string()
+            }
+        else {
+            format!("Enter the name or path of the sound theme, or leave empty for the default sounds. Available themes: {}.", themes.join(", "))
+            };
+
+        if let Some(theme)=self.input_box("Sound theme", &message) {
+            let theme=theme.trim().to_string();
+
+            if !Sounds::theme_exists(&theme) {
+                self.speech.speak_notification(&format!("Unknown theme {}", theme));
+                return;
+                }
+
+            self.settings.sounds.theme=theme;
+            self.sounds=Sounds::new(&self.audio, &self.settings.sounds);
+
+            if !self.sounds.errors.is_empty() {
+                self.speech.speak_notification(&format!("Unable to load sounds, {}", self.sounds.errors.join(", ")));
+                }
+            else if self.settings.sounds.theme.is_empty() {
+                self.speech.speak_notification("Default sounds");
+                }
+            else {
+                self.speech.speak_notification(&format!("Sound theme {}", self.settings.sounds.theme));
                 }
             }
         }
@@ -486,133 +821,181 @@ impl<'a> RideScreen<'a> {
             func(self);
             }
         else if !(key.control() ^ key.alt()) {
-            if let Some(character) = key.keyval().to_unicode() {
+            if let Some(character) = key.character() {
                 if !character.is_control() {
                     self.on_text_entered(character);
                     }
                 }
             }
-        }
 
-    fn on_text_entered(&mut self, character: char) {
-        self.content.insert(character);
+        self.update_view();
         }
 
-    pub fn on_exit(&self) {
-        self.settings.save(&Settings::get_settings_file_path("ride", "settings.yaml"));
-        }
+    //Frontends get the lines of the current level after every change
 
-    fn speak_text(&self, text: &str) {
-        self.speech.speak(&self.settings.text_renderer.render_text(text));
-        }
+    pub fn update_view(&mut self) {
+        let mut view=LevelView::new(&self.content, VIEW_CAPACITY);
 
-    fn speak_character(&mut self, character: char) {
-        if let Some(rendered_character)=self.settings.text_renderer.render_character(character) {
-            self.speech.speak(&rendered_character);
-            }
-        else {
-            self.speech.speak_char(&character.to_string());
+        if let Some(braille)=&mut self.braille {
+            let text=view.current_line().map_or("", |line| line.text.as_str());
+            braille.show_line(view.line_number, view.depth, text, view.character_offset);
+            view.braille_window=Some(braille.window());
             }
 
-        if character.is_uppercase() && self.settings.beep_on_capital_characters {
-            self.resources.capital.play();
-            }
+        self.frontend.show(view);
         }
 
-    pub fn message_box(&self, title: &str, message: &str) {
-        let (title, message)=(title.to_string(), message.to_string());
-        let (message_box_sender, message_box_receiver)=std::sync::mpsc::channel::<()>();
+    //Frontends call this periodically to process the keys of the braille display
+
+    pub fn poll_braille(&mut self) {
+        let action=match &mut self.braille {
+            Some(braille) => braille.poll(),
+            None => return,
+            };
+
+        match action {
+            Some(BrailleAction::MoveCaret(character_offset)) => {
+                if self.content.navigate_to_character(character_offset).is_ok() {
+                    let character=self.content.get_current_character();
+                    self.speak_character(character);
+                    }
+                },
+            Some(BrailleAction::PreviousLine) => self.navigate_to_previous_line(),
+            Some(BrailleAction::NextLine) => self.navigate_to_next_line(),
+            None => return,
+            };
 
-        glib::source::idle_add_once(move || {
-            let dialog = gtk::Dialog::new();
-            dialog.set_title(&title);
+        self.update_view();
+        }
 
-            let label = gtk::Label::new(Some(&message));
+    fn on_text_entered(&mut self, character: char) {
+        self.content.insert(character);
+        self.echo_typed_character(character);
+        }
 
-            dialog.content_area().add(&label);
+    //When both are echoed, the finished word and the character following it are spoken together, so neither interrupts the other
 
-            dialog.add_button("Ok", gtk::ResponseType::Ok);
+    fn echo_typed_character(&mut self, character: char) {
+        let echo=self.settings.typing_echo;
+        let word=if echo.echoes_words() {
+            typed_word(&self.content.get_current_line(), self.content.current_character_offset())
+            }
+        else {
+            None
+            };
 
-            dialog.show_all();
+        match word {
+            Some(word) => {
+                let mut text=self.settings.text_renderer.render_text(&word);
 
-            dialog.run();
+                if echo.echoes_characters() {
+                    let rendered_character=self.settings.text_renderer.render_character(character).unwrap_or_else(|| character.to_string());
+                    text=format!("{} {}", text, rendered_character);
+                    }
 
-            dialog.close();
+                //The character finishing a word is never a capital, so the beep follows the word
 
-            message_box_sender.send(()).unwrap();
-            });
+                if word.chars().any(char::is_uppercase) && self.settings.beep_on_capital_characters {
+                    self.play_sound(SoundEvent::Capital);
+                    }
 
-        message_box_receiver.recv().unwrap();
+                self.speech.speak(&text);
+                },
+            None if echo.echoes_characters() => self.speak_character(character),
+            None => {},
+            };
         }
 
-    pub fn input_box(&self, title: &str, message: &str) -> Option<String> {
-        let (title, message)=(title.to_string(), message.to_string());
-        let (input_box_sender, input_box_receiver)=std::sync::mpsc::channel::<Option<String>>();
+    pub fn on_exit(&self) {
+        self.settings.save(&Settings::get_settings_file_path("ride", "settings.yaml"));
+        }
 
-        glib::source::idle_add_once(move || {
-            let dialog = std::rc::Rc::new(gtk::Dialog::new());
-            dialog.set_title(&title);
+    fn caret_pan(&self) -> f32 {
+        self.earcons.pan(self.content.current_character_offset(), self.content.get_current_line().chars().count())
+        }
 
-            let label = gtk::Label::new(Some(&message));
-            let entry = gtk::Entry::new();
+    fn play_sound(&self, event: SoundEvent) {
+        self.sounds.play(event, self.caret_pan());
+        }
 
-            dialog.content_area().add(&label);
-            dialog.content_area().add(&entry);
+    //Depth tones replace the sounds of entering and leaving levels, when enabled
 
-            dialog.add_button("Ok", gtk::ResponseType::Ok);
-            dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+    fn play_level_change(&self, event: SoundEvent) {
+        if self.earcons.has_depth_tones() {
+            self.earcons.play_depth(self.content.current_indentation_level(), self.caret_pan());
+            }
+        else {
+            self.play_sound(event);
+            }
+        }
 
-            let dialog_clone=dialog.clone();
-            entry.connect_key_press_event(move |_, key| {
-                if key.keyval()==gdk::keys::constants::Return {
-                    dialog_clone.response(gtk::ResponseType::Ok);
-                    return Propagation::Stop;
-                    }
+    fn play_level_change_from(&self, original_indentation_level: usize) {
+        let indentation_level=self.content.current_indentation_level();
 
-                Propagation::Proceed
-                });
+        if indentation_level>original_indentation_level {
+            self.play_level_change(SoundEvent::LevelEntered);
+            }
+        else if indentation_level<original_indentation_level {
+            self.play_level_change(SoundEvent::LevelLeft);
+            }
+        }
 
-            dialog.show_all();
+    fn play_block_tone(&self) {
+        if self.content.current_line_has_subblock() {
+            self.earcons.play_block(self.content.current_indentation_level(), self.caret_pan());
+            }
+        }
 
-            let response=dialog.run();
+    fn speak_current_line(&self) {
+        let text=self.settings.text_renderer.render_text(&self.content.get_current_line());
+        self.speech.speak_line(&text, self.content.current_indentation_level(), self.content.current_line_has_subblock());
+        }
 
-            let result=if response==gtk::ResponseType::Ok {
-                Some(entry.text().to_string())
-                }
-            else {
-                None
-                };
+    fn speak_character(&mut self, character: char) {
+        if let Some(rendered_character)=self.settings.text_renderer.render_character(character) {
+            self.speech.speak(&rendered_character);
+            }
+        else {
+            self.speech.speak_char(&character.to_string());
+            }
 
-            dialog.close();
+        if character.is_uppercase() && self.settings.beep_on_capital_characters {
+            self.play_sound(SoundEvent::Capital);
+            }
+        }
 
-            input_box_sender.send(result).unwrap();
-            });
+    pub fn message_box(&self, title: &str, message: &str) {
+        self.frontend.message_box(title, message);
+        }
 
-        input_box_receiver.recv().unwrap()
+    pub fn input_box(&self, title: &str, message: &str) -> Option<String> {
+        self.frontend.input_box(title, message)
         }
 
     pub fn clipboard_get_text(&mut self) -> String {
-        if let Ok(text)=self.clipboard_context.get_contents() {
-            return text;
+        if let Some(clipboard_context)=&mut self.clipboard_context {
+            if let Ok(text)=clipboard_context.get_contents() {
+                return text;
+                }
             }
 
-        String::new()
+        self.clipboard_text.clone()
         }
 
     pub fn clipboard_set_text(&mut self, text: &str) {
-        self.clipboard_context.set_contents(text.to_string()).unwrap();
-        }
+        if let Some(clipboard_context)=&mut self.clipboard_context {
+            if clipboard_context.set_contents(text.to_string()).is_ok() {
+                return;
+                }
+            }
 
-    }
+        self.clipboard_text=text.to_string();
+        }
 
-pub enum GtkThreadMessage {
-    KeyPress(KeyboardShortcut),
-    //WindowCreation(Arc<ApplicationWindow>),
-    ApplicationExit,
-    }
+    pub fn set_speech_listener(&mut self, listener: Sender<String>) {
+        self.speech.set_listener(listener);
+        }
 
-pub enum RideThreadMessage {
-    SetWindowTitle(String),
     }
 
 impl Default for Settings {
@@ -622,10 +1005,47 @@ impl Default for Settings {
         }
 
     }
+
+//The word finished by the character just before the caret, if that character can't be a part of an identifier
+
+fn typed_word(line: &str, character_offset: usize) -> Option<String> {
+    let is_word_character=|character: &char| character.is_alphanumeric() || *character=='_';
+    let characters: Vec<char>=line.chars().take(character_offset).collect();
+    let (last_character, preceding_characters)=characters.split_last()?;
+
+    if is_word_character(last_character) {
+        return None;
+        }
+
+    let beginning=preceding_characters.iter().rposition(|character| !is_word_character(character)).map_or(0, |i| i+1);
+    let word: String=preceding_characters[beginning..].iter().collect();
+
+    if word.is_empty() {
+        None
+        }
+    else {
+        Some(word)
+        }
+    }
+
 #[cfg(test)]
 mod tests {
+
+    use super::*;
+
     #[test]
     fn it_works() {
         assert_eq!(2 + 2, 4);
         }
+
+    #[test]
+    fn words_are_finished_by_non_identifier_characters() {
+        assert_eq!(typed_word("print(my_value)", 9), None);
+        assert_eq!(typed_word("print(my_value)", 6), Some("print".to_string()));
+        assert_eq!(typed_word("print(my_value)", 15), Some("my_value".to_string()));
+        assert_eq!(typed_word("x = ", 4), None);
+        assert_eq!(typed_word("x = ", 2), Some("x".to_string()));
+        assert_eq!(typed_word("", 0), None);
+        }
+
     }
@@ -15,104 +15,39 @@
 */
 
 use std::env;
-use std::rc::Rc;
-use std::sync::{mpsc, mpsc::{Receiver, Sender}};
-use std::thread;
-use std::time::Duration;
+use std::process;
 
-use gtk::prelude::*;
-
-use gtk::{Application, ApplicationWindow};
-use glib::{ControlFlow, Propagation};
-
-use gio::ApplicationFlags;
-
-mod core;
+mod braille;
+mod earcons;
 mod screen;
+mod sounds;
 mod speech;
 mod interface;
-
-use interface::{RideScreen, GtkThreadMessage, RideThreadMessage};
-use screen::KeyboardShortcut;
+#[cfg(feature="gui")]
+mod gui;
+mod tui;
+mod view;
 
 fn main() {
-    bass::Sound::init();
-
-    let (gtk_sender, ride_receiver) = mpsc::channel::<GtkThreadMessage>();
-    let (ride_sender, gtk_receiver) = mpsc::channel::<RideThreadMessage>();
-    let gtk_receiver=Rc::new(gtk_receiver);
-
-    let handle=launch_ride_thread(ride_sender, ride_receiver);
-
-    let application=Application::new(None, ApplicationFlags::HANDLES_OPEN);
-
-    connect_application_activate_handler(&application, gtk_sender.clone(), gtk_receiver.clone());
-    connect_application_open_handler(&application, gtk_sender.clone(), gtk_receiver.clone());
-
-    application.run();
-
-    gtk_sender.send(GtkThreadMessage::ApplicationExit).unwrap();
-    handle.join().unwrap();
+    let arguments: Vec<String>=env::args().skip(1).collect();
+    let file_path=arguments.iter().find(|i| *i!="--tui").cloned().unwrap_or_default();
+
+    if !use_terminal(&arguments) {
+        #[cfg(feature="gui")]
+        gui::run(file_path);
+        }
+    else if let Err(error)=tui::run(&file_path) {
+        eprintln!("{}", error);
+        process::exit(1);
+        }
     }
 
-fn activate_window(app: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
-
-    let window=Rc::new(ApplicationWindow::new(app));
-    window.set_title("Ride");
-    window.set_default_size(350, 70);
-
-    connect_key_press_handler(window.clone(), gtk_sender);
-    setup_timer(window.clone(), gtk_receiver);
+//The terminal frontend is used on request, in builds without GTK and where no display is available
 
-    window.show_all();
-    }
-
-fn launch_ride_thread(ride_sender: Sender<RideThreadMessage>, ride_receiver: Receiver<GtkThreadMessage>) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        let file_path=match env::args().nth(1) {
-            Some(path) => path,
-            None => "".to_string(),
-            };
-
-        let mut ride_screen=RideScreen::new(&file_path, ride_sender);
-
-        for received in ride_receiver {
-            match received {
-                GtkThreadMessage::KeyPress(key) => ride_screen.on_key_pressed(&key),
-                GtkThreadMessage::ApplicationExit => break,
-                };
-            }
-
-        ride_screen.on_exit();
-        })
-    }
-fn connect_application_activate_handler(application: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
-    application.connect_activate(move |app| {
-        activate_window(app, gtk_sender.clone(), gtk_receiver.clone());
-        });
-    }
-fn connect_application_open_handler(application: &Application, gtk_sender: Sender<GtkThreadMessage>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
-    application.connect_open(move |app, _, _| {
-        activate_window(app, gtk_sender.clone(), gtk_receiver.clone());
-        });
-    }
-
-fn connect_key_press_handler(window: Rc<ApplicationWindow>, gtk_sender: Sender<GtkThreadMessage>) {
-    window.connect_key_press_event(move |_, key| {
-        let keyboard_shortcut=KeyboardShortcut::from_eventkey(key);
-        gtk_sender.send(GtkThreadMessage::KeyPress(keyboard_shortcut)).unwrap();
-
-        Propagation::Proceed
-        });
-    }
-fn setup_timer(window: Rc<ApplicationWindow>, gtk_receiver: Rc<Receiver<RideThreadMessage>>) {
-    glib::source::timeout_add_local(Duration::from_millis(100), move || {
-        if let Ok(message) = gtk_receiver.try_recv() {
-            match message {
-                RideThreadMessage::SetWindowTitle(title) => window.set_title(&title),
-                };
-            }
+fn use_terminal(arguments: &[String]) -> bool {
+    if !cfg!(feature="gui") || arguments.iter().any(|i| i=="--tui") {
+        return true;
+        }
 
-        ControlFlow::Continue
-        });
+    cfg!(all(unix, not(target_os="macos"))) && env::var_os("DISPLAY").is_none() && env::var_os("WAYLAND_DISPLAY").is_none()
     }
@@ -20,3 +20,23 @@ mod imp;
 
 pub use imp::*;
 
+
+impl Key {
+
+    pub fn from_character(character: char) -> Option<Key> {
+        match character.to_ascii_lowercase() {
+            'x' => Some(Key::X),
+            'c' => Some(Key::C),
+            'v' => Some(Key::V),
+            's' => Some(Key::S),
+            'f' => Some(Key::F),
+            'j' => Some(Key::J),
+            'r' => Some(Key::R),
+            'i' => Some(Key::I),
+            'l' => Some(Key::L),
+            'm' => Some(Key::M),
+            _ => None,
+            }
+        }
+
+    }
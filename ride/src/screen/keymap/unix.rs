@@ -33,6 +33,11 @@ pub enum Key {
     J=44,
     R=27,
     I=31,
+    PageUp=112,
+    PageDown=117,
+    L=46,
+    M=58,
+    Control=37,
     F3=69,
     }
 
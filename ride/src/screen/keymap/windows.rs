@@ -33,6 +33,11 @@ pub enum Key {
     J=74,
     R=82,
     I=73,
+    PageUp=33,
+    PageDown=34,
+    L=76,
+    M=77,
+    Control=17,
     F3=114,
     }
 
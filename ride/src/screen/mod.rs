@@ -53,49 +53,56 @@ impl<'a, T> KeyboardShortcutsManager<'a, T> {
 #[derive(Derivative)]
 #[derivative(Eq, PartialEq, Hash)]
 pub struct KeyboardShortcut {
-    modifiers: gdk::ModifierType,
+    control: bool,
+    shift: bool,
+    alt: bool,
     key: u16,
     #[derivative(PartialEq="ignore")]
     #[derivative(Hash="ignore")]
-    keyval: gdk::keys::Key,
+    character: Option<char>,
     }
 
 impl KeyboardShortcut {
 
     pub fn new(control: bool, shift: bool, alt: bool, key: Key) -> KeyboardShortcut {
-        let mut modifiers: gdk::ModifierType=gdk::ModifierType::empty();
+        KeyboardShortcut {control, shift, alt, key: key as u16, character: None}
+        }
 
-        if control {
-            modifiers.insert(gdk::ModifierType::CONTROL_MASK);
-            }
-        if shift {
-            modifiers.insert(gdk::ModifierType::SHIFT_MASK);
-            }
-        if alt {
-            modifiers.insert(gdk::ModifierType::MOD1_MASK);
-            }
+    //Key presses of frontends without hardware keycodes, the key is derived from the character if possible
 
-        let key=key as u16;
+    pub fn from_character(control: bool, alt: bool, character: char) -> KeyboardShortcut {
+        let shift=character.is_uppercase();
+        let key=Key::from_character(character).map_or(0, |key| key as u16);
 
-        KeyboardShortcut {modifiers, key, keyval: gdk::keys::Key::from_unicode(' ')}
+        KeyboardShortcut {control, shift, alt, key, character: Some(character)}
         }
 
-    pub fn from_eventkey(eventkey: &gdk::EventKey) -> KeyboardShortcut {
-        let modifiers=eventkey.state()
-        .intersection(gdk::ModifierType::CONTROL_MASK | gdk::ModifierType::SHIFT_MASK | gdk::ModifierType::MOD1_MASK);
+    pub fn from_key(control: bool, shift: bool, alt: bool, key: Key, character: Option<char>) -> KeyboardShortcut {
+        KeyboardShortcut {control, shift, alt, key: key as u16, character}
+        }
 
-        KeyboardShortcut {modifiers, key: eventkey.hardware_keycode(), keyval: eventkey.keyval()}
+    #[cfg(feature="gui")]
+    pub fn from_eventkey(eventkey: &gdk::EventKey) -> KeyboardShortcut {
+        let modifiers=eventkey.state();
+
+        KeyboardShortcut {
+            control: modifiers.contains(gdk::ModifierType::CONTROL_MASK),
+            shift: modifiers.contains(gdk::ModifierType::SHIFT_MASK),
+            alt: modifiers.contains(gdk::ModifierType::MOD1_MASK),
+            key: eventkey.hardware_keycode(),
+            character: eventkey.keyval().to_unicode(),
+            }
         }
 
     pub fn control(&self) -> bool {
-        self.modifiers.contains(gdk::ModifierType::CONTROL_MASK)
+        self.control
         }
     pub fn alt(&self) -> bool {
-        self.modifiers.contains(gdk::ModifierType::MOD1_MASK)
+        self.alt
         }
 
-    pub fn keyval(&self) -> &gdk::keys::Key {
-        &self.keyval
+    pub fn character(&self) -> Option<char> {
+        self.character
         }
 
     }
@@ -116,6 +123,15 @@ mod tests {
         assert_eq!(hm.contains_key(&k2), true);
         }
 
+    #[test]
+    fn characters_are_mapped_to_keys() {
+        let k1=KeyboardShortcut::new(true, true, false, Key::R);
+        let k2=KeyboardShortcut::from_character(true, false, 'R');
+
+        assert!(k1==k2);
+        assert!(KeyboardShortcut::from_character(false, false, 'a').character()==Some('a'));
+        }
+
     #[test]
     fn enums_cast_test() {
         let k=Key::Up;
@@ -0,0 +1,278 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bass::{Audio, Sound};
+use serde::{Serialize, Deserialize};
+
+//The default sounds are embedded, so Ride has them wherever it's run from
+
+const DEFAULT_SOUNDS: [(&str, &[u8]); 3]=[
+    ("Bump.wav", include_bytes!("../Sounds/Bump.wav")),
+    ("Chil.wav", include_bytes!("../Sounds/Chil.wav")),
+    ("Capital.wav", include_bytes!("../Sounds/Capital.wav")),
+    ];
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all="snake_case")]
+pub enum SoundEvent {
+    Boundary,
+    LevelEntered,
+    LevelLeft,
+    Capital,
+    Saved,
+    Error,
+    SelectionStarted,
+    Paste,
+    SearchWrapped,
+    NotFound,
+    }
+
+impl SoundEvent {
+
+    pub const ALL: [SoundEvent; 10]=[
+        SoundEvent::Boundary,
+        SoundEvent::LevelEntered,
+        SoundEvent::LevelLeft,
+        SoundEvent::Capital,
+        SoundEvent::Saved,
+        SoundEvent::Error,
+        SoundEvent::SelectionStarted,
+        SoundEvent::Paste,
+        SoundEvent::SearchWrapped,
+        SoundEvent::NotFound,
+        ];
+
+    }
+
+//The sound is a file name looked up in the theme, volume ranges from 0 to 1
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventSound {
+    pub enabled: bool,
+    pub sound: String,
+    pub volume: f32,
+    }
+
+impl EventSound {
+
+    fn new(enabled: bool, sound: &str) -> EventSound {
+        EventSound {enabled, sound: sound.to_string(), volume: 1.0}
+        }
+
+    }
+
+impl Default for EventSound {
+
+    fn default() -> Self {
+        EventSound::new(false, "")
+        }
+
+    }
+
+//An empty theme uses the default sounds, events missing in the settings keep their defaults
+//The volume applies to all sounds and earcons, max voices limit how many times a sound can overlap itself
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub theme: String,
+    pub volume: f32,
+    pub max_voices: u32,
+    pub events: BTreeMap<SoundEvent, EventSound>,
+    }
+
+impl SoundSettings {
+
+    pub fn event(&self, event: SoundEvent) -> EventSound {
+        self.events.get(&event).cloned().unwrap_or_else(|| SoundSettings::default_event(event))
+        }
+
+    fn default_event(event: SoundEvent) -> EventSound {
+        match event {
+            SoundEvent::Boundary => EventSound::new(true, "Bump.wav"),
+            SoundEvent::LevelEntered | SoundEvent::LevelLeft => EventSound::new(true, "Chil.wav"),
+            SoundEvent::Capital => EventSound::new(true, "Capital.wav"),
+            SoundEvent::Error | SoundEvent::NotFound => EventSound::new(false, "Bump.wav"),
+            _ => EventSound::default(),
+            }
+        }
+
+    }
+
+impl Default for SoundSettings {
+
+    fn default() -> Self {
+        let events=SoundEvent::ALL.iter().map(|event| (*event, SoundSettings::default_event(*event))).collect();
+
+        SoundSettings {theme: String::new(), volume: 1.0, max_voices: 4, events}
+        }
+
+    }
+
+//Sounds which couldn't be loaded are left out and the reasons collected in errors
+
+pub struct Sounds {
+    sounds: BTreeMap<SoundEvent, (Sound, f32)>,
+    pub errors: Vec<String>,
+    }
+
+impl Sounds {
+
+    pub fn new(audio: &Audio, settings: &SoundSettings) -> Sounds {
+        let directories=Sounds::theme_directories(&settings.theme);
+        let mut sounds=BTreeMap::new();
+        let mut errors=Vec::new();
+
+        for event in SoundEvent::ALL.iter() {
+            let event_sound=settings.event(*event);
+
+            if !event_sound.enabled || event_sound.sound.is_empty() {
+                continue;
+                }
+
+            if let Some(sound)=Sounds::load(audio, &directories, &event_sound.sound, &mut errors) {
+                sounds.insert(*event, (sound, event_sound.volume));
+                }
+            }
+
+        Sounds {sounds, errors}
+        }
+
+    pub fn play(&self, event: SoundEvent, pan: f32) {
+        if let Some((sound, volume))=self.sounds.get(&event) {
+            sound.play_with(pan, *volume).ok();
+            }
+        }
+
+    //Themes are the subdirectories of the sound directories
+
+    pub fn themes() -> Vec<String> {
+        let mut themes: Vec<String>=Sounds::sound_directories().iter()
+        .filter_map(|directory| fs::read_dir(directory).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+
+        themes.sort();
+        themes.dedup();
+        themes
+        }
+
+    //The user's directory comes first, then the system wide ones and the directory of the executable
+
+    fn sound_directories() -> Vec<PathBuf> {
+        let mut directories=Vec::new();
+
+        if let Some(data_dir)=dirs::data_dir() {
+            directories.push(data_dir.join("ride").join("sounds"));
+            }
+
+        if cfg!(unix) {
+            let data_dirs=env::var_os("XDG_DATA_DIRS").filter(|data_dirs| !data_dirs.is_empty()).unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+            directories.extend(env::split_paths(&data_dirs).map(|data_dir| data_dir.join("ride").join("sounds")));
+            }
+
+        if let Some(root)=env::current_exe().ok().as_deref().and_then(Path::parent) {
+            directories.push(root.join("Sounds"));
+            }
+
+        directories
+        }
+
+    //A theme is either a name of a subdirectory of the sound directories, or a path
+
+    fn theme_directories(theme: &str) -> Vec<PathBuf> {
+        if theme.is_empty() {
+            return Sounds::sound_directories();
+            }
+
+        if Path::new(theme).is_absolute() {
+            return vec![PathBuf::from(theme)];
+            }
+
+        Sounds::sound_directories().iter().map(|directory| directory.join(theme)).collect()
+        }
+
+    pub fn theme_exists(theme: &str) -> bool {
+        theme.is_empty() || Sounds::theme_directories(theme).iter().any(|directory| directory.is_dir())
+        }
+
+    fn find(directories: &[PathBuf], file_name: &str) -> Option<PathBuf> {
+        directories.iter().map(|directory| directory.join(file_name)).find(|path| path.is_file())
+        }
+
+    //A file which fails to load is reported and the embedded sound is used instead, if there is one
+
+    fn load(audio: &Audio, directories: &[PathBuf], file_name: &str, errors: &mut Vec<String>) -> Option<Sound> {
+        if let Some(path)=Sounds::find(directories, file_name) {
+            match audio.load(&path.to_string_lossy()) {
+                Ok(sound) => return Some(sound),
+                Err(error) => errors.push(format!("{}: {}", file_name, error)),
+                }
+            }
+
+        match DEFAULT_SOUNDS.iter().find(|(name, _)| *name==file_name) {
+            Some((_, data)) => audio.load_from_memory(data).map_err(|error| errors.push(format!("{}: {}", file_name, error))).ok(),
+            None => {
+                errors.push(format!("{}: Not found", file_name));
+                None
+                },
+            }
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn sounds_are_looked_up_in_order() {
+        let root=env::temp_dir().join("ride-sound-lookup");
+        let directories=vec![root.join("user"), root.join("system")];
+        fs::create_dir_all(&directories[0]).unwrap();
+        fs::create_dir_all(&directories[1]).unwrap();
+        fs::write(directories[1].join("Bump.wav"), "").unwrap();
+        fs::write(directories[1].join("Chil.wav"), "").unwrap();
+        fs::write(directories[0].join("Chil.wav"), "").unwrap();
+
+        assert_eq!(Sounds::find(&directories, "Bump.wav"), Some(directories[1].join("Bump.wav")));
+        assert_eq!(Sounds::find(&directories, "Chil.wav"), Some(directories[0].join("Chil.wav")));
+        assert_eq!(Sounds::find(&directories, "Capital.wav"), None);
+        assert!(DEFAULT_SOUNDS.iter().all(|(_, data)| data.starts_with(b"RIFF")));
+        fs::remove_dir_all(root).unwrap();
+        }
+
+    #[test]
+    fn missing_events_keep_their_defaults() {
+        let settings: SoundSettings=serde_yaml::from_str("events:\n  boundary:\n    enabled: false\n  paste:\n    enabled: true\n    sound: Paste.wav\n    volume: 0.5\n").unwrap();
+
+        assert!(!settings.event(SoundEvent::Boundary).enabled);
+        assert_eq!(settings.event(SoundEvent::Paste).sound, "Paste.wav");
+        assert_eq!(settings.event(SoundEvent::Paste).volume, 0.5);
+        assert!(settings.event(SoundEvent::Capital).enabled);
+        assert_eq!(settings.theme, "");
+        }
+
+    }
@@ -0,0 +1,199 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use super::{SpeechBackend, VoiceSettings};
+
+const PROGRAM: &str="espeak-ng";
+const QUEUE_POLLING_INTERVAL: Duration=Duration::from_millis(20);
+
+enum EspeakCommand {
+    Speak(Vec<String>, String),
+    Stop,
+    }
+
+//Runs eSpeak NG for every utterance, a thread starts the queued utterances once the previous one finishes
+
+pub struct EspeakBackend {
+    sender: Sender<EspeakCommand>,
+    voice_arguments: RefCell<Vec<String>>,
+    }
+
+impl EspeakBackend {
+
+    pub fn new() -> Result<EspeakBackend, String> {
+        let status=Command::new(PROGRAM).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status()
+        .map_err(|error| format!("Unable to run {}: {}", PROGRAM, error))?;
+
+        if !status.success() {
+            return Err(format!("{} failed", PROGRAM));
+            }
+
+        let (sender, receiver)=mpsc::channel();
+        thread::spawn(move || EspeakBackend::process_queue(receiver));
+
+        Ok(EspeakBackend {sender, voice_arguments: RefCell::new(Vec::new())})
+        }
+
+    fn say(&self, arguments: &[&str], text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        let mut all_arguments=self.voice_arguments.borrow().clone();
+        all_arguments.extend(arguments.iter().map(|argument| argument.to_string()));
+
+        self.sender.send(EspeakCommand::Speak(all_arguments, text.to_string())).ok();
+        }
+
+    //The thread ends when the backend is dropped together with its sender
+
+    fn process_queue(receiver: Receiver<EspeakCommand>) {
+        let mut process: Option<Child>=None;
+        let mut queue=VecDeque::new();
+
+        loop {
+            match receiver.recv_timeout(QUEUE_POLLING_INTERVAL) {
+                Ok(EspeakCommand::Speak(arguments, text)) => queue.push_back((arguments, text)),
+                Ok(EspeakCommand::Stop) => {
+                    queue.clear();
+                    EspeakBackend::kill(&mut process);
+                    },
+                Err(RecvTimeoutError::Timeout) => {},
+                Err(RecvTimeoutError::Disconnected) => {
+                    EspeakBackend::kill(&mut process);
+                    return;
+                    },
+                }
+
+            let finished=match &mut process {
+                Some(child) => !matches!(child.try_wait(), Ok(None)),
+                None => true,
+                };
+
+            if finished {
+                process=queue.pop_front().and_then(|(arguments, text)| EspeakBackend::spawn(&arguments, &text));
+                }
+            }
+        }
+
+    //The text goes through the standard input, so it can't be taken for options
+
+    fn spawn(arguments: &[String], text: &str) -> Option<Child> {
+        let mut process=Command::new(PROGRAM).args(arguments)
+        .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::null())
+        .spawn().ok()?;
+
+        if let Some(mut stdin)=process.stdin.take() {
+            stdin.write_all(text.as_bytes()).ok();
+            }
+
+        Some(process)
+        }
+
+    fn kill(process: &mut Option<Child>) {
+        if let Some(mut process)=process.take() {
+            process.kill().ok();
+            process.wait().ok();
+            }
+        }
+
+    //Words per minute range from 80 to 450 with 175 by default, pitch from 0 to 99 with 50 and amplitude from 0 to 200 with 100 at the full volume
+
+    fn voice_arguments(voice: &VoiceSettings) -> Vec<String> {
+        let rate=if voice.rate<0 { 175+voice.rate*95/100 } else { 175+voice.rate*275/100 };
+        let mut arguments=vec![
+            "-s".to_string(), rate.to_string(),
+            "-p".to_string(), (50+voice.pitch/2).clamp(0, 99).to_string(),
+            "-a".to_string(), ((voice.volume+100)/2).to_string(),
+            ];
+
+        let voice_name=if !voice.voice.is_empty() { &voice.voice } else { &voice.language };
+        if !voice_name.is_empty() {
+            arguments.push("-v".to_string());
+            arguments.push(voice_name.clone());
+            }
+
+        arguments
+        }
+
+    }
+
+impl SpeechBackend for EspeakBackend {
+
+    fn name(&self) -> &str {
+        PROGRAM
+        }
+
+    fn speak(&self, text: &str, interrupt: bool) {
+        self.say(&[], text, interrupt);
+        }
+
+    fn speak_char(&self, text: &str, interrupt: bool) {
+        if text!=" " {
+            self.say(&["--punct"], text, interrupt);
+            }
+        else {
+            self.say(&[], "space", interrupt);
+            }
+        }
+
+    fn stop(&self) {
+        self.sender.send(EspeakCommand::Stop).ok();
+        }
+
+    fn speak_ssml(&self, ssml: &str, _text: &str, interrupt: bool) {
+        self.say(&["-m"], ssml, interrupt);
+        }
+
+    fn set_voice(&self, voice: &VoiceSettings) {
+        *self.voice_arguments.borrow_mut()=EspeakBackend::voice_arguments(voice);
+        }
+
+    //The voices of eSpeak NG are selected by their language
+
+    fn voices(&self) -> Vec<String> {
+        let output=match Command::new(PROGRAM).arg("--voices").stderr(Stdio::null()).output() {
+            Ok(output) => String::from_utf8_lossy(&output.stdout).to_string(),
+            Err(_) => return Vec::new(),
+            };
+
+        output.lines().skip(1).filter_map(|line| line.split_whitespace().nth(1).map(|language| language.to_string())).collect()
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn voice_settings_are_converted_to_arguments() {
+        let voice=VoiceSettings {rate: 100, pitch: -100, volume: 0, language: "sk".to_string(), ..VoiceSettings::default()};
+
+        assert_eq!(EspeakBackend::voice_arguments(&voice), vec!["-s", "450", "-p", "0", "-a", "50", "-v", "sk"]);
+        assert_eq!(EspeakBackend::voice_arguments(&VoiceSettings::default())[..6], ["-s", "175", "-p", "50", "-a", "100"]);
+        }
+
+    }
@@ -0,0 +1,106 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+
+use super::{SpeechBackend, VoiceSettings};
+
+//Appends the spoken text to a file, one utterance per line, for automated tests
+
+pub struct LogBackend {
+    file: RefCell<File>,
+    }
+
+impl LogBackend {
+
+    pub fn new(file_path: &str) -> Result<LogBackend, String> {
+        if file_path.is_empty() {
+            return Err("No speech log file set".to_string());
+            }
+
+        let file=OpenOptions::new().create(true).append(true).open(file_path)
+        .map_err(|error| format!("Unable to open {}: {}", file_path, error))?;
+
+        Ok(LogBackend {file: RefCell::new(file)})
+        }
+
+    fn write(&self, kind: &str, text: &str) {
+        writeln!(self.file.borrow_mut(), "{}\t{}", kind, text.replace('\n', " ")).ok();
+        }
+
+    }
+
+impl SpeechBackend for LogBackend {
+
+    fn name(&self) -> &str {
+        "log"
+        }
+
+    fn speak(&self, text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        self.write("speak", text);
+        }
+
+    fn speak_char(&self, text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        self.write("char", text);
+        }
+
+    fn stop(&self) {
+        self.write("stop", "");
+        }
+
+    fn speak_ssml(&self, ssml: &str, _text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        self.write("ssml", ssml);
+        }
+
+    fn set_voice(&self, voice: &VoiceSettings) {
+        self.write("voice", &format!("rate={} pitch={} volume={} module={} voice={} language={}", voice.rate, voice.pitch, voice.volume, voice.module, voice.voice, voice.language));
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn utterances_are_logged() {
+        let file_path=std::env::temp_dir().join("ride-speech.log");
+        let file_path=file_path.to_str().unwrap();
+
+        let backend=LogBackend::new(file_path).unwrap();
+        backend.speak("def a\n", false);
+        backend.speak_char("(", false);
+
+        assert_eq!(std::fs::read_to_string(file_path).unwrap(), "speak\tdef a \nchar\t(\n");
+        std::fs::remove_file(file_path).unwrap();
+        }
+
+    }
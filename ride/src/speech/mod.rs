@@ -14,30 +14,279 @@
 * along with this program. If not, see <https://www.gnu.org/licenses/>.
 */
 
-#[cfg_attr(unix, path="unix.rs")]
-#[cfg_attr(windows, path="windows.rs")]
-mod imp;
+#[cfg(unix)]
+mod speech_dispatcher;
+#[cfg(windows)]
+mod tolk;
+mod espeak;
+mod log;
+mod prosody;
+mod silent;
 
-pub struct Speech(imp::Speech);
+use std::cell::RefCell;
+use std::sync::mpsc::Sender;
+
+use serde::{Serialize, Deserialize};
+
+use espeak::EspeakBackend;
+use log::LogBackend;
+use silent::SilentBackend;
+
+pub use prosody::ProsodySettings;
+
+pub trait SpeechBackend {
+    fn name(&self) -> &str;
+    fn speak(&self, text: &str, interrupt: bool);
+    fn speak_char(&self, text: &str, interrupt: bool);
+    fn stop(&self);
+
+    //Backends without SSML support speak the plain text instead
+
+    fn speak_ssml(&self, _ssml: &str, text: &str, interrupt: bool) {
+        self.speak(text, interrupt);
+        }
+
+    fn set_voice(&self, _voice: &VoiceSettings) {
+        }
+
+    fn voices(&self) -> Vec<String> {
+        Vec::new()
+        }
+    }
+
+//Rate, pitch and volume range from -100 to 100, empty strings leave the defaults of the backend
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceSettings {
+    pub rate: i32,
+    pub pitch: i32,
+    pub volume: i32,
+    pub module: String,
+    pub voice: String,
+    pub language: String,
+    }
+
+impl Default for VoiceSettings {
+
+    fn default() -> Self {
+        VoiceSettings {rate: 0, pitch: 0, volume: 100, module: String::new(), voice: String::new(), language: String::new()}
+        }
+
+    }
+
+//Navigation is what is read after moving around the text, notifications report the results of commands
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum SpeechPriority {
+    Interrupt,
+    Queue,
+    }
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpeechPriorities {
+    pub navigation: SpeechPriority,
+    pub notification: SpeechPriority,
+    }
+
+impl Default for SpeechPriorities {
+
+    fn default() -> Self {
+        SpeechPriorities {navigation: SpeechPriority::Interrupt, notification: SpeechPriority::Queue}
+        }
+
+    }
+
+//Native is speech-dispatcher on Linux and the running screen reader through Tolk on Windows, SAPI is the Windows synthesizer
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum SpeechBackendKind {
+    Native,
+    Sapi,
+    Espeak,
+    Log,
+    Silent,
+    }
+
+pub struct Speech {
+    backend: Box<dyn SpeechBackend>,
+    listener: Option<Sender<String>>,
+    unheard_text: RefCell<Option<String>>,
+    muted: bool,
+    priorities: SpeechPriorities,
+    prosody: ProsodySettings,
+    }
 impl Speech {
 
-    pub fn new(app_name: &str) -> Speech {
-        Speech(imp::Speech::new(app_name))
+    //The first backend which can be opened is used, falling back to silence
+
+    pub fn new(app_name: &str, backends: &[SpeechBackendKind], log_file: &str) -> Speech {
+        let backend=backends.iter()
+        .find_map(|kind| Speech::open_backend(*kind, app_name, log_file).ok())
+        .unwrap_or_else(|| Box::new(SilentBackend));
+
+        Speech {backend, listener: None, unheard_text: RefCell::new(None), muted: false, priorities: SpeechPriorities::default(), prosody: ProsodySettings::default()}
+        }
+
+    fn open_backend(kind: SpeechBackendKind, app_name: &str, log_file: &str) -> Result<Box<dyn SpeechBackend>, String> {
+        Ok(match kind {
+            #[cfg(unix)]
+            SpeechBackendKind::Native => Box::new(speech_dispatcher::SpeechDispatcherBackend::new(app_name)?),
+            #[cfg(windows)]
+            SpeechBackendKind::Native => Box::new(tolk::TolkBackend::new(false)?),
+            #[cfg(windows)]
+            SpeechBackendKind::Sapi => Box::new(tolk::TolkBackend::new(true)?),
+            #[cfg(not(windows))]
+            SpeechBackendKind::Sapi => return Err("SAPI is available only on Windows".to_string()),
+            SpeechBackendKind::Espeak => Box::new(EspeakBackend::new()?),
+            SpeechBackendKind::Log => Box::new(LogBackend::new(log_file)?),
+            SpeechBackendKind::Silent => Box::new(SilentBackend),
+            })
+        }
+
+    pub fn backend_name(&self) -> &str {
+        self.backend.name()
+        }
+
+    pub fn set_voice(&self, voice: &VoiceSettings) {
+        self.backend.set_voice(voice);
+        }
+
+    pub fn voices(&self) -> Vec<String> {
+        self.backend.voices()
+        }
+
+    pub fn set_priorities(&mut self, priorities: &SpeechPriorities) {
+        self.priorities=priorities.clone();
+        }
+
+    pub fn set_prosody(&mut self, prosody: &ProsodySettings) {
+        self.prosody=prosody.clone();
+        }
+
+    //Muted speech is still passed to the listener
+
+    pub fn set_muted(&mut self, muted: bool) {
+        self.muted=muted;
+        }
+
+    //The listener receives everything spoken, so frontends can display it
+    //The last text spoken before it was set is passed to it as well, like problems found at the startup
+
+    pub fn set_listener(&mut self, listener: Sender<String>) {
+        if let Some(text)=self.unheard_text.take() {
+            listener.send(text).ok();
+            }
+
+        self.listener=Some(listener);
         }
 
     pub fn speak(&self, text: &str) {
-        self.0.speak(text);
+        if !self.muted {
+            self.backend.speak(text, self.priorities.navigation==SpeechPriority::Interrupt);
+            }
+
+        self.notify(text);
         }
 
     pub fn speak_char(&self, text: &str) {
-        self.0.speak_char(text);
+        if !self.muted {
+            self.backend.speak_char(text, self.priorities.navigation==SpeechPriority::Interrupt);
+            }
+
+        self.notify(text);
+        }
+
+    //Lines are spoken with the prosody reflecting their place in the structure, if enabled
+
+    pub fn speak_line(&self, text: &str, depth: usize, has_block: bool) {
+        if !self.prosody.is_enabled() {
+            self.speak(text);
+            return;
+            }
+
+        if !self.muted {
+            self.backend.speak_ssml(&self.prosody.to_ssml(text, depth, has_block), text, self.priorities.navigation==SpeechPriority::Interrupt);
+            }
+
+        self.notify(text);
+        }
+
+    pub fn speak_notification(&self, text: &str) {
+        if !self.muted {
+            self.backend.speak(text, self.priorities.notification==SpeechPriority::Interrupt);
+            }
+
+        self.notify(text);
+        }
+
+    pub fn stop(&self) {
+        self.backend.stop();
+        }
+
+    fn notify(&self, text: &str) {
+        match &self.listener {
+            Some(listener) => {
+                listener.send(text.to_string()).ok();
+                },
+            None => {
+                self.unheard_text.replace(Some(text.to_string()));
+                },
+            }
         }
     }
 
 #[cfg(test)]
 mod tests {
+
+    use super::*;
+
     #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn unavailable_backends_are_skipped() {
+        let speech=Speech::new("ride", &[SpeechBackendKind::Log, SpeechBackendKind::Silent], "/nonexistent/speech.log");
+        assert_eq!(speech.backend_name(), "silent");
+
+        let speech=Speech::new("ride", &[], "");
+        assert_eq!(speech.backend_name(), "silent");
         }
+
+    #[test]
+    fn muted_speech_reaches_the_listener() {
+        let log_file=std::env::temp_dir().join("ride-muted-speech.log");
+        let log_file=log_file.to_str().unwrap();
+        let (sender, receiver)=std::sync::mpsc::channel();
+
+        let mut speech=Speech::new("ride", &[SpeechBackendKind::Log], log_file);
+        speech.set_muted(true);
+        speech.speak("Unable to load sounds");
+        speech.set_listener(sender);
+        speech.speak("Hello");
+
+        assert_eq!(receiver.try_recv(), Ok("Unable to load sounds".to_string()));
+        assert_eq!(receiver.try_recv(), Ok("Hello".to_string()));
+        assert_eq!(std::fs::read_to_string(log_file).unwrap(), "");
+        std::fs::remove_file(log_file).unwrap();
+        }
+
+    #[test]
+    fn notifications_are_queued_after_navigation() {
+        let log_file=std::env::temp_dir().join("ride-speech-priorities.log");
+        let log_file=log_file.to_str().unwrap();
+
+        let mut speech=Speech::new("ride", &[SpeechBackendKind::Log], log_file);
+        speech.speak("def a():");
+        speech.speak_notification("Copied");
+        speech.speak_char("(");
+
+        speech.set_priorities(&SpeechPriorities {navigation: SpeechPriority::Queue, notification: SpeechPriority::Interrupt});
+        speech.speak("return 0");
+        speech.speak_notification("Pasted");
+
+        assert_eq!(std::fs::read_to_string(log_file).unwrap(), "stop\t\nspeak\tdef a():\nspeak\tCopied\nstop\t\nchar\t(\nspeak\treturn 0\nstop\t\nspeak\tPasted\n");
+        std::fs::remove_file(log_file).unwrap();
+        }
+
     }
@@ -0,0 +1,104 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use serde::{Serialize, Deserialize};
+
+//What the pitch of a line reflects, the depth of the line or whether it introduces a block
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all="lowercase")]
+pub enum ProsodyStructure {
+    None,
+    Depth,
+    Block,
+    }
+
+//The pitch step is in percents, depth voices are used in turns, the first one on the top level
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProsodySettings {
+    pub structure: ProsodyStructure,
+    pub pitch_step: i32,
+    pub depth_voices: Vec<String>,
+    }
+
+impl ProsodySettings {
+
+    pub fn is_enabled(&self) -> bool {
+        self.structure!=ProsodyStructure::None || !self.depth_voices.is_empty()
+        }
+
+    pub fn to_ssml(&self, text: &str, depth: usize, has_block: bool) -> String {
+        let pitch=match self.structure {
+            ProsodyStructure::None => 0,
+            ProsodyStructure::Depth => self.pitch_step*depth as i32,
+            ProsodyStructure::Block if has_block => self.pitch_step,
+            ProsodyStructure::Block => 0,
+            };
+
+        let mut ssml=escape(text);
+        if pitch!=0 {
+            ssml=format!("<prosody pitch=\"{:+}%\">{}</prosody>", pitch, ssml);
+            }
+        if !self.depth_voices.is_empty() {
+            ssml=format!("<voice name=\"{}\">{}</voice>", escape(&self.depth_voices[depth%self.depth_voices.len()]), ssml);
+            }
+
+        format!("<speak>{}</speak>", ssml)
+        }
+
+    }
+
+impl Default for ProsodySettings {
+
+    fn default() -> Self {
+        ProsodySettings {structure: ProsodyStructure::None, pitch_step: 10, depth_voices: Vec::new()}
+        }
+
+    }
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn pitch_follows_the_structure() {
+        let prosody=ProsodySettings {structure: ProsodyStructure::Depth, ..ProsodySettings::default()};
+
+        assert_eq!(prosody.to_ssml("if a<b:", 0, true), "<speak>if a&lt;b:</speak>");
+        assert_eq!(prosody.to_ssml("return a", 2, false), "<speak><prosody pitch=\"+20%\">return a</prosody></speak>");
+
+        let prosody=ProsodySettings {structure: ProsodyStructure::Block, pitch_step: -15, ..ProsodySettings::default()};
+        assert_eq!(prosody.to_ssml("if a<b:", 1, true), "<speak><prosody pitch=\"-15%\">if a&lt;b:</prosody></speak>");
+        assert_eq!(prosody.to_ssml("return a", 1, false), "<speak>return a</speak>");
+        }
+
+    #[test]
+    fn depth_voices_are_used_in_turns() {
+        let prosody=ProsodySettings {depth_voices: vec!["en".to_string(), "en-gb".to_string()], ..ProsodySettings::default()};
+
+        assert!(prosody.is_enabled());
+        assert_eq!(prosody.to_ssml("x", 2, false), "<speak><voice name=\"en\">x</voice></speak>");
+        assert_eq!(prosody.to_ssml("x", 3, false), "<speak><voice name=\"en-gb\">x</voice></speak>");
+        }
+
+    }
@@ -1,5 +1,5 @@
 /*
-* Copyright (C) 2022 Rastislav Kish
+* Copyright (C) 2023 Rastislav Kish
 *
 * This program is free software: you can redistribute it and/or modify
 * it under the terms of the GNU General Public License as published by
@@ -14,34 +14,23 @@
 * along with this program. If not, see <https://www.gnu.org/licenses/>.
 */
 
-use std::sync::Arc;
+use super::SpeechBackend;
 
-use tolk::Tolk;
+pub struct SilentBackend;
 
-pub struct Speech {
-    tolk: Arc<Tolk>,
-    }
-impl Speech {
-
-    pub fn new(_app_name: &str) -> Speech {
-        let tolk=Tolk::new();
+impl SpeechBackend for SilentBackend {
 
-        Speech { tolk }
+    fn name(&self) -> &str {
+        "silent"
         }
 
-    pub fn speak(&self, text: &str) {
-        self.tolk.speak(text, true);
+    fn speak(&self, _text: &str, _interrupt: bool) {
         }
 
-    pub fn speak_char(&self, text: &str) {
-        self.tolk.speak(text, true);
+    fn speak_char(&self, _text: &str, _interrupt: bool) {
         }
-    }
 
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
+    fn stop(&self) {
         }
+
     }
@@ -0,0 +1,160 @@
+/*
+* Copyright (C) 2022 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cell::{Cell, RefCell};
+
+use speech_dispatcher::{Connection, DataMode, Voice};
+
+use super::{SpeechBackend, VoiceSettings};
+
+//Speech Dispatcher can't set the default module or language back, a new connection starts with them instead
+
+pub struct SpeechDispatcherBackend {
+    app_name: String,
+    connection: RefCell<Connection>,
+    //Whether the connection got a module and a language of its own
+    applied: Cell<(bool, bool)>,
+    }
+
+impl SpeechDispatcherBackend {
+
+    pub fn new(app_name: &str) -> Result<SpeechDispatcherBackend, String> {
+        let connection=SpeechDispatcherBackend::open(app_name)?;
+
+        Ok(SpeechDispatcherBackend {app_name: app_name.to_string(), connection: RefCell::new(connection), applied: Cell::new((false, false))})
+        }
+
+    fn open(app_name: &str) -> Result<Connection, String> {
+        let connection_name=format!("{}-connection", app_name);
+
+        Connection::open(app_name, &connection_name[..], "", speech_dispatcher::Mode::Threaded)
+        .map_err(|error| format!("Unable to connect to Speech Dispatcher: {:?}", error))
+        }
+
+    }
+
+impl SpeechBackend for SpeechDispatcherBackend {
+
+    fn name(&self) -> &str {
+        "speech-dispatcher"
+        }
+
+    //Cancelling discards also the queued messages, not only the one being spoken
+
+    fn speak(&self, text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        self.connection.borrow().say(speech_dispatcher::Priority::Text, text);
+        }
+
+    fn speak_char(&self, text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        if text!=" " {
+            self.connection.borrow().char(speech_dispatcher::Priority::Text, text).ok();
+            }
+        else {
+            self.connection.borrow().char(speech_dispatcher::Priority::Text, "space").ok();
+            }
+        }
+
+    fn stop(&self) {
+        self.connection.borrow().cancel().ok();
+        }
+
+    //The data mode is taken with every message, so the following ones are plain text again
+
+    fn speak_ssml(&self, ssml: &str, _text: &str, interrupt: bool) {
+        if interrupt {
+            self.stop();
+            }
+
+        let connection=self.connection.borrow();
+        connection.set_data_mode(DataMode::SSML).ok();
+        connection.say(speech_dispatcher::Priority::Text, ssml);
+        connection.set_data_mode(DataMode::Text).ok();
+        }
+
+    //The module is set first, as it determines the available voices
+
+    fn set_voice(&self, voice: &VoiceSettings) {
+        if needs_reconnection(self.applied.get(), voice) {
+            if let Ok(connection)=SpeechDispatcherBackend::open(&self.app_name) {
+                self.connection.replace(connection);
+                self.applied.set((false, false));
+                }
+            }
+
+        let connection=self.connection.borrow();
+        let (module_applied, language_applied)=self.applied.get();
+
+        if !voice.module.is_empty() {
+            connection.set_output_module(voice.module.as_str()).ok();
+            }
+        if !voice.language.is_empty() {
+            connection.set_language(voice.language.as_str()).ok();
+            }
+        self.applied.set((module_applied || !voice.module.is_empty(), language_applied || !voice.language.is_empty()));
+        if !voice.voice.is_empty() {
+            connection.set_synthesis_voice(&Voice {name: voice.voice.clone(), language: String::new(), variant: None}).ok();
+            }
+
+        connection.set_voice_rate(voice.rate).ok();
+        connection.set_voice_pitch(voice.pitch).ok();
+        connection.set_volume(voice.volume).ok();
+        }
+
+    fn voices(&self) -> Vec<String> {
+        match self.connection.borrow().list_synthesis_voices() {
+            Ok(voices) => voices.into_iter().map(|voice| voice.name).collect(),
+            Err(_) => Vec::new(),
+            }
+        }
+
+    }
+
+//Only clearing a module or language applied before needs the defaults of a new connection
+
+fn needs_reconnection((module_applied, language_applied): (bool, bool), voice: &VoiceSettings) -> bool {
+    (module_applied && voice.module.is_empty()) || (language_applied && voice.language.is_empty())
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn only_cleared_settings_reconnect() {
+        let mut voice=VoiceSettings {module: "espeak-ng".to_string(), ..VoiceSettings::default()};
+
+        assert!(!needs_reconnection((false, false), &voice));
+        assert!(!needs_reconnection((true, false), &voice));
+
+        voice.module.clear();
+        assert!(needs_reconnection((true, false), &voice));
+        assert!(!needs_reconnection((false, false), &voice));
+
+        voice.language="sk".to_string();
+        assert!(!needs_reconnection((false, true), &voice));
+        assert!(needs_reconnection((true, true), &voice));
+        }
+
+    }
@@ -0,0 +1,68 @@
+/*
+* Copyright (C) 2022 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::sync::Arc;
+
+use tolk::Tolk;
+
+use super::SpeechBackend;
+
+pub struct TolkBackend {
+    tolk: Arc<Tolk>,
+    }
+impl TolkBackend {
+
+    //Without SAPI, Tolk speaks only through a running screen reader, so the backend fails to open without one
+
+    pub fn new(sapi: bool) -> Result<TolkBackend, String> {
+        let tolk=Tolk::new();
+        tolk.try_sapi(sapi);
+
+        if !sapi && tolk.detect_screen_reader().is_none() {
+            return Err("No screen reader is running".to_string());
+            }
+
+        Ok(TolkBackend { tolk })
+        }
+
+    }
+
+impl SpeechBackend for TolkBackend {
+
+    fn name(&self) -> &str {
+        "Tolk"
+        }
+
+    fn speak(&self, text: &str, interrupt: bool) {
+        self.tolk.speak(text, interrupt);
+        }
+
+    fn speak_char(&self, text: &str, interrupt: bool) {
+        self.tolk.speak(text, interrupt);
+        }
+
+    fn stop(&self) {
+        self.tolk.silence();
+        }
+    }
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn it_works() {
+        assert_eq!(2 + 2, 4);
+        }
+    }
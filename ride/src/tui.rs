@@ -0,0 +1,355 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::cell::{Cell, RefCell};
+use std::io::{self, Write};
+use std::sync::{mpsc, mpsc::Receiver};
+use std::time::Duration;
+
+use crossterm::{cursor, execute, queue, terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, PushKeyboardEnhancementFlags, PopKeyboardEnhancementFlags};
+use crossterm::style::{Attribute, Print, SetAttribute};
+
+use crate::interface::{Appearance, Frontend, RideScreen, Settings};
+use crate::screen::{KeyboardShortcut, Key};
+use crate::speech::Speech;
+use crate::view::LevelView;
+
+const BRAILLE_POLLING_INTERVAL: Duration=Duration::from_millis(100);
+
+pub fn run(file_path: &str) -> io::Result<()> {
+    let terminal=Terminal::open()?;
+    let (speech_sender, speech_receiver)=mpsc::channel::<String>();
+
+    let settings=Settings::load();
+    let mut speech=Speech::new("ride-dialogs", &settings.speech.backends(), &settings.speech.log_file);
+    speech.set_voice(&settings.speech.voice);
+    speech.set_priorities(&settings.speech.priorities);
+
+    //Sighted users see what the editor speaks through until something is spoken
+    let status=format!("Speech: {}", speech.backend_name());
+
+    let frontend=TerminalFrontend {
+        title: RefCell::new(String::new()),
+        status: RefCell::new(status),
+        speech_receiver,
+        speech,
+        screen_reader_mode: Cell::new(false),
+        };
+    let mut ride_screen=RideScreen::new(file_path, Box::new(frontend));
+    ride_screen.set_speech_listener(speech_sender);
+    ride_screen.update_view();
+
+    loop {
+        if !event::poll(BRAILLE_POLLING_INTERVAL)? {
+            ride_screen.poll_braille();
+            continue;
+            }
+
+        let event=match event::read()? {
+            Event::Key(event) if event.kind!=KeyEventKind::Release => event,
+            Event::Resize(_, _) => {
+                ride_screen.update_view();
+                continue;
+                },
+            _ => continue,
+            };
+
+        if event.modifiers.contains(KeyModifiers::CONTROL) && event.code==KeyCode::Char('q') {
+            break;
+            }
+
+        //Terminals don't report a bare Control, so Escape stops the speech instead
+
+        if event.code==KeyCode::Esc && event.modifiers.is_empty() {
+            ride_screen.stop_speech();
+            continue;
+            }
+
+        if let Some(keyboard_shortcut)=to_keyboard_shortcut(&event) {
+            ride_screen.on_key_pressed(&keyboard_shortcut);
+            }
+        }
+
+    ride_screen.on_exit();
+    drop(terminal);
+
+    Ok(())
+    }
+
+fn to_keyboard_shortcut(event: &KeyEvent) -> Option<KeyboardShortcut> {
+    let control=event.modifiers.contains(KeyModifiers::CONTROL);
+    let shift=event.modifiers.contains(KeyModifiers::SHIFT);
+    let alt=event.modifiers.contains(KeyModifiers::ALT);
+
+    let key=match event.code {
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Backspace => Key::Backspace,
+        KeyCode::Enter => Key::Return,
+        KeyCode::F(3) => Key::F3,
+        KeyCode::Char(character) if shift => return Some(KeyboardShortcut::from_character(control, alt, character.to_ascii_uppercase())),
+        KeyCode::Char(character) => return Some(KeyboardShortcut::from_character(control, alt, character)),
+        _ => return None,
+        };
+
+    Some(KeyboardShortcut::from_key(control, shift, alt, key, None))
+    }
+
+//Draws the title, the lines of the current level under the line introducing them and the status line
+
+fn draw(title: &str, view: &LevelView, status: &str) -> io::Result<()> {
+    let (width, height)=terminal::size()?;
+    let (width, height)=(width as usize, height as usize);
+    let mut stdout=io::stdout();
+
+    queue!(stdout, terminal::Clear(terminal::ClearType::All), cursor::MoveTo(0, 0))?;
+    queue!(stdout, SetAttribute(Attribute::Reverse), Print(fit(title, width)), SetAttribute(Attribute::Reset))?;
+
+    let mut row=1;
+    let mut caret=(0, 1);
+    let prefix=if view.parent_line.is_some() { "    " } else { "" };
+
+    if let Some(line)=&view.parent_line {
+        queue!(stdout, cursor::MoveTo(0, row as u16), Print(fit(&line.text, width)))?;
+
+        if line.current {
+            caret=(view.character_offset, row);
+            }
+
+        row+=1;
+        }
+
+    //Blocks of the listed lines are marked by an ellipsis
+
+    let reserved_rows=if view.braille_window.is_some() { 2 } else { 1 };
+    let rows=height.saturating_sub(row+reserved_rows);
+    let current_index=view.lines.iter().position(|line| line.current).unwrap_or(0);
+    let first_index=current_index.saturating_sub(rows/2).min(view.lines.len().saturating_sub(rows));
+
+    for line in view.lines.iter().skip(first_index).take(rows) {
+        let text=format!("{}{}{}", prefix, line.text, if line.has_block { " ..." } else { "" });
+        let attribute=if line.selected { Attribute::Reverse } else { Attribute::Reset };
+
+        queue!(stdout, cursor::MoveTo(0, row as u16), SetAttribute(attribute), Print(fit(&text, width)), SetAttribute(Attribute::Reset))?;
+
+        if line.current {
+            caret=(prefix.len()+view.character_offset, row);
+            }
+
+        row+=1;
+        }
+
+    //The window of the braille display is shown above the status line
+
+    if let Some((text, cursor))=&view.braille_window {
+        let cursor=cursor.map_or(String::new(), |cursor| format!(" (cursor {})", cursor+1));
+        queue!(stdout, cursor::MoveTo(0, height.saturating_sub(2) as u16), Print(fit(&format!("Braille: {}{}", text, cursor), width)))?;
+        }
+
+    let status=if status.is_empty() { view.status() } else { format!("{} | {}", view.status(), status) };
+    queue!(stdout, cursor::MoveTo(0, height.saturating_sub(1) as u16), SetAttribute(Attribute::Reverse), Print(fit(&status, width)), SetAttribute(Attribute::Reset))?;
+
+    queue!(stdout, cursor::MoveTo(caret.0.min(width.saturating_sub(1)) as u16, caret.1 as u16))?;
+    stdout.flush()
+    }
+
+//Cuts the text to the width of the terminal
+
+fn fit(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+    }
+
+fn draw_prompt(text: &str) -> io::Result<()> {
+    let (width, height)=terminal::size()?;
+    let mut stdout=io::stdout();
+    let text=fit(text, width as usize);
+
+    queue!(stdout, cursor::MoveTo(0, height.saturating_sub(1)), terminal::Clear(terminal::ClearType::CurrentLine))?;
+    queue!(stdout, SetAttribute(Attribute::Reverse), Print(&text), SetAttribute(Attribute::Reset))?;
+    queue!(stdout, cursor::MoveTo(text.chars().count().min(width.saturating_sub(1) as usize) as u16, height.saturating_sub(1)))?;
+    stdout.flush()
+    }
+
+fn read_key() -> io::Result<KeyEvent> {
+    loop {
+        if let Event::Key(event)=event::read()? {
+            if event.kind!=KeyEventKind::Release {
+                return Ok(event);
+                }
+            }
+        }
+    }
+
+struct TerminalFrontend {
+    title: RefCell<String>,
+    status: RefCell<String>,
+    speech_receiver: Receiver<String>,
+    speech: Speech,
+    screen_reader_mode: Cell<bool>,
+    }
+
+impl TerminalFrontend {
+
+    //Terminal screen readers read the prompts and follow the cursor themselves
+
+    fn speak(&self, text: &str) {
+        if !self.screen_reader_mode.get() {
+            self.speech.speak(text);
+            }
+        }
+
+    fn speak_char(&self, text: &str) {
+        if !self.screen_reader_mode.get() {
+            self.speech.speak_char(text);
+            }
+        }
+
+    fn read_line(&self, message: &str) -> io::Result<Option<String>> {
+        let mut input=String::new();
+
+        loop {
+            draw_prompt(&format!("{} {}", message, input))?;
+
+            let event=read_key()?;
+            match event.code {
+                KeyCode::Enter => return Ok(Some(input)),
+                KeyCode::Esc => return Ok(None),
+                KeyCode::Backspace => {
+                    if let Some(character)=input.pop() {
+                        self.speak_char(&character.to_string());
+                        }
+                    },
+                KeyCode::Char(character) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    input.push(character);
+                    self.speak_char(&character.to_string());
+                    },
+                _ => {},
+                };
+            }
+        }
+
+    }
+
+impl Frontend for TerminalFrontend {
+
+    fn set_title(&self, title: &str) {
+        *self.title.borrow_mut()=title.to_string();
+        }
+
+    fn message_box(&self, title: &str, message: &str) {
+        self.speak(&format!("{}: {}", title, message));
+
+        if draw_prompt(&format!("{}: {} Press any key to continue.", title, message)).is_ok() {
+            read_key().ok();
+            }
+        }
+
+    fn input_box(&self, title: &str, message: &str) -> Option<String> {
+        self.speak(&format!("{}: {}", title, message));
+
+        self.read_line(message).unwrap_or(None)
+        }
+
+    fn show(&self, view: LevelView) {
+        //The status line shows the last spoken text, so the terminal is usable without speech as well
+
+        while let Ok(text)=self.speech_receiver.try_recv() {
+            *self.status.borrow_mut()=text;
+            }
+
+        draw(&self.title.borrow(), &view, &self.status.borrow()).ok();
+        }
+
+    //Fonts and colors are left to the terminal
+
+    fn set_appearance(&self, _appearance: &Appearance) {
+        }
+
+    fn set_screen_reader_mode(&self, enabled: bool) {
+        self.screen_reader_mode.set(enabled);
+        }
+
+    }
+
+//Puts the terminal into raw mode and restores it when dropped, even on panic
+
+struct Terminal {
+    keyboard_enhancement: bool,
+    }
+
+impl Terminal {
+
+    fn open() -> io::Result<Terminal> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen)?;
+
+        //Without the enhancement, terminals can't tell apart keys like Ctrl+I and Tab
+
+        let keyboard_enhancement=terminal::supports_keyboard_enhancement().unwrap_or(false);
+        if keyboard_enhancement {
+            execute!(io::stdout(), PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES))?;
+            }
+
+        Ok(Terminal {keyboard_enhancement})
+        }
+
+    fn restore(&self) -> io::Result<()> {
+        if self.keyboard_enhancement {
+            execute!(io::stdout(), PopKeyboardEnhancementFlags)?;
+            }
+
+        execute!(io::stdout(), terminal::LeaveAlternateScreen)?;
+        terminal::disable_raw_mode()
+        }
+
+    }
+
+impl Drop for Terminal {
+
+    fn drop(&mut self) {
+        self.restore().ok();
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn key_events_are_translated() {
+        let event=KeyEvent::new(KeyCode::Char('i'), KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+        assert!(to_keyboard_shortcut(&event).unwrap()==KeyboardShortcut::new(true, true, false, Key::I));
+
+        let event=KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        assert!(to_keyboard_shortcut(&event).unwrap()==KeyboardShortcut::new(false, true, false, Key::Return));
+
+        let event=KeyEvent::new(KeyCode::PageUp, KeyModifiers::CONTROL | KeyModifiers::ALT);
+        assert!(to_keyboard_shortcut(&event).unwrap()==KeyboardShortcut::new(true, false, true, Key::PageUp));
+
+        assert!(to_keyboard_shortcut(&KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE)).is_none());
+        }
+
+    }
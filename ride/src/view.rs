@@ -0,0 +1,121 @@
+/*
+* Copyright (C) 2023 Rastislav Kish
+*
+* This program is free software: you can redistribute it and/or modify
+* it under the terms of the GNU General Public License as published by
+* the Free Software Foundation, version 3.
+*
+* This program is distributed in the hope that it will be useful,
+* but WITHOUT ANY WARRANTY; without even the implied warranty of
+* MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+* GNU General Public License for more details.
+*
+* You should have received a copy of the GNU General Public License
+* along with this program. If not, see <https://www.gnu.org/licenses/>.
+*/
+
+use std::path::Path;
+
+use ride_core::RideText;
+
+//A snapshot of the current level for frontends displaying the text
+
+pub struct LevelView {
+    pub parent_line: Option<ViewLine>,
+    pub lines: Vec<ViewLine>,
+    pub line_number: usize,
+    pub character_offset: usize,
+    pub depth: usize,
+    pub file_name: String,
+    pub braille_window: Option<(String, Option<usize>)>,
+    }
+
+pub struct ViewLine {
+    pub text: String,
+    pub has_block: bool,
+    pub current: bool,
+    pub selected: bool,
+    }
+
+impl LevelView {
+
+    //Takes at most the given count of lines of the current level, centered around the caret
+
+    pub fn new(content: &RideText, capacity: usize) -> LevelView {
+        let area=content.current_area();
+        let depth=content.current_indentation_level();
+        let line_number=content.current_line_number();
+        let selection=content.selection().unwrap_or(0..0);
+
+        let view_line=|line_number: usize, text: &str, has_block: bool| ViewLine {
+            text: text.to_string(),
+            has_block,
+            current: line_number==content.current_line_number(),
+            selected: selection.contains(&line_number),
+            };
+
+        let parent_line=if depth>0 && area.start>0 {
+            content.lines(area.start-1, area.start).next().map(|(_, text)| view_line(area.start-1, text, true))
+            }
+        else {
+            None
+            };
+
+        let mut lines: Vec<(usize, &str, bool)>=Vec::new();
+        for (index, (indentation_level, text)) in content.lines(area.start, area.end).enumerate() {
+            if indentation_level==depth {
+                lines.push((area.start+index, text, false));
+                }
+            else if let Some(line)=lines.last_mut() {
+                if area.start+index==line.0+1 {
+                    line.2=true;
+                    }
+                }
+            }
+
+        let current_index=lines.iter().position(|line| line.0==line_number).unwrap_or(0);
+        let first_index=current_index.saturating_sub(capacity/2).min(lines.len().saturating_sub(capacity));
+        let lines=lines.into_iter().skip(first_index).take(capacity).map(|(line_number, text, has_block)| view_line(line_number, text, has_block)).collect();
+
+        let file_name=match content.file_path() {
+            Some(file_path) => Path::new(file_path).file_name().map_or(file_path.to_string(), |i| i.to_string_lossy().to_string()),
+            None => "Untitled".to_string(),
+            };
+
+        LevelView {parent_line, lines, line_number, character_offset: content.current_character_offset(), depth, file_name, braille_window: None}
+        }
+
+    pub fn current_line(&self) -> Option<&ViewLine> {
+        self.parent_line.iter().chain(self.lines.iter()).find(|line| line.current)
+        }
+
+    pub fn status(&self) -> String {
+        format!("Line {}, column {}, depth {}, {}", self.line_number+1, self.character_offset+1, self.depth+1, self.file_name)
+        }
+
+    }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn current_level_is_taken() {
+        let mut content=RideText::new();
+        content.load("class Foo\n    def a\n        pass\n    def b\n    def c\nclass Bar\n".to_string()).unwrap();
+
+        content.increase_indentation_level().unwrap();
+        content.navigate_to_next_line().unwrap();
+        content.navigate_to_next_line().unwrap();
+        let view=LevelView::new(&content, 2);
+
+        assert_eq!(view.parent_line.as_ref().unwrap().text, "class Foo");
+        assert_eq!(view.lines.iter().map(|i| i.text.as_str()).collect::<Vec<&str>>(), vec!["def a", "def b"]);
+        assert!(view.lines[0].has_block && !view.lines[0].current);
+        assert!(view.lines[1].current && !view.lines[1].has_block);
+        assert_eq!(view.current_line().unwrap().text, "def b");
+        assert_eq!(view.status(), "Line 4, column 1, depth 2, Untitled");
+        }
+
+    }